@@ -0,0 +1,259 @@
+// Rules for the application the text comes from, e.g. translate to English in the IDE
+// and never translate anything from the password manager
+use crate::config::AppConfig;
+use crate::window::WindowOptions;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceWindow {
+    pub title: String,
+    pub class: String,
+    pub process: String,
+}
+
+// Patterns are case insensitive substrings, all the set ones have to match
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppRule {
+    pub title: String,
+    pub class: String,
+    pub process: String,
+    pub target: Option<String>,
+    pub services: Option<Vec<String>>,
+    pub auto_copy: Option<String>,
+    pub ignore: bool,
+}
+
+impl AppRule {
+    pub fn is_valid(&self) -> bool {
+        let language = match &self.target {
+            Some(v) => !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            None => true,
+        };
+        let auto_copy = match &self.auto_copy {
+            Some(v) => ["source", "target", "source_target", "disable"].contains(&v.as_str()),
+            None => true,
+        };
+        let services = match &self.services {
+            Some(v) => !v.is_empty(),
+            None => true,
+        };
+        language && auto_copy && services
+    }
+
+    fn matches(&self, window: &SourceWindow) -> bool {
+        let patterns = [
+            (&self.title, &window.title),
+            (&self.class, &window.class),
+            (&self.process, &window.process),
+        ];
+        // A rule without patterns would match every application
+        if patterns.iter().all(|(pattern, _)| pattern.is_empty()) {
+            return false;
+        }
+        patterns.iter().all(|(pattern, value)| {
+            pattern.is_empty() || value.to_lowercase().contains(&pattern.to_lowercase())
+        })
+    }
+
+    // Options given with the request win over the rule
+    pub fn apply(&self, options: &mut WindowOptions) {
+        if options.target.is_none() {
+            options.target = self.target.clone();
+        }
+        if options.services.is_none() {
+            options.services = self.services.clone();
+        }
+        if options.auto_copy.is_none() {
+            options.auto_copy = self.auto_copy.clone();
+        }
+    }
+}
+
+// The first rule matching the focused window, the source of the selection or clipboard text
+pub fn active_rule() -> Option<AppRule> {
+    let rules = AppConfig::load().app_rules;
+    if rules.is_empty() {
+        return None;
+    }
+    let window = active_window()?;
+    let rule = rules.into_iter().find(|rule| rule.matches(&window))?;
+    info!("App rule matched {:?}", window);
+    Some(rule)
+}
+
+#[cfg(target_os = "windows")]
+pub fn active_window() -> Option<SourceWindow> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::{CloseHandle, FALSE};
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetClassNameW, GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+    };
+
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return None;
+    }
+    let mut buffer = [0u16; 512];
+    let len = unsafe { GetWindowTextW(hwnd, &mut buffer) };
+    let title = String::from_utf16_lossy(&buffer[..len.max(0) as usize]);
+    let len = unsafe { GetClassNameW(hwnd, &mut buffer) };
+    let class = String::from_utf16_lossy(&buffer[..len.max(0) as usize]);
+
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32)) };
+    let mut process = String::new();
+    if let Ok(handle) = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) } {
+        let mut size = buffer.len() as u32;
+        let result = unsafe {
+            QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut size,
+            )
+        };
+        if result.is_ok() {
+            let path = String::from_utf16_lossy(&buffer[..size as usize]);
+            // `C:\...\Code.exe` is matched as `Code.exe`
+            process = path.rsplit('\\').next().unwrap_or_default().to_string();
+        }
+        let _ = unsafe { CloseHandle(handle) };
+    }
+    Some(SourceWindow {
+        title,
+        class,
+        process,
+    })
+}
+
+// X11 only, Wayland doesn't tell which window is focused
+#[cfg(target_os = "linux")]
+pub fn active_window() -> Option<SourceWindow> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window};
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen_num].root;
+    let atom = |name: &str| -> Option<Atom> {
+        Some(
+            conn.intern_atom(false, name.as_bytes())
+                .ok()?
+                .reply()
+                .ok()?
+                .atom,
+        )
+    };
+    let property = |window: Window, name: &str, kind: Atom| {
+        conn.get_property(false, window, atom(name)?, kind, 0, 1024)
+            .ok()?
+            .reply()
+            .ok()
+    };
+
+    let window = property(root, "_NET_ACTIVE_WINDOW", AtomEnum::WINDOW.into())?
+        .value32()?
+        .next()?;
+    if window == 0 {
+        return None;
+    }
+    let title = match property(window, "_NET_WM_NAME", atom("UTF8_STRING")?) {
+        Some(reply) if !reply.value.is_empty() => String::from_utf8_lossy(&reply.value).to_string(),
+        _ => property(window, "WM_NAME", AtomEnum::STRING.into())
+            .map(|reply| String::from_utf8_lossy(&reply.value).to_string())
+            .unwrap_or_default(),
+    };
+    // WM_CLASS is `instance\0class\0`
+    let class = property(window, "WM_CLASS", AtomEnum::STRING.into())
+        .map(|reply| {
+            String::from_utf8_lossy(&reply.value)
+                .rsplit('\0')
+                .find(|v| !v.is_empty())
+                .unwrap_or_default()
+                .to_string()
+        })
+        .unwrap_or_default();
+    let process = property(window, "_NET_WM_PID", AtomEnum::CARDINAL.into())
+        .and_then(|reply| reply.value32()?.next())
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default();
+    Some(SourceWindow {
+        title,
+        class,
+        process,
+    })
+}
+
+#[cfg(target_os = "macos")]
+pub fn active_window() -> Option<SourceWindow> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window() -> SourceWindow {
+        SourceWindow {
+            title: "main.rs - pot - Visual Studio Code".to_string(),
+            class: "Chrome_WidgetWin_1".to_string(),
+            process: "Code.exe".to_string(),
+        }
+    }
+
+    #[test]
+    fn matches_case_insensitive_substrings() {
+        let rule = AppRule {
+            process: "code".to_string(),
+            ..Default::default()
+        };
+        assert!(rule.matches(&window()));
+        let rule = AppRule {
+            title: "VISUAL STUDIO".to_string(),
+            class: "chrome_widgetwin".to_string(),
+            ..Default::default()
+        };
+        assert!(rule.matches(&window()));
+    }
+
+    #[test]
+    fn matches_needs_every_set_pattern() {
+        let rule = AppRule {
+            title: "Visual Studio".to_string(),
+            process: "firefox".to_string(),
+            ..Default::default()
+        };
+        assert!(!rule.matches(&window()));
+    }
+
+    #[test]
+    fn matches_nothing_without_patterns() {
+        assert!(!AppRule::default().matches(&window()));
+        assert!(!AppRule::default().matches(&SourceWindow::default()));
+    }
+
+    #[test]
+    fn apply_keeps_the_request_options() {
+        let rule = AppRule {
+            process: "code".to_string(),
+            target: Some("en".to_string()),
+            services: Some(vec!["deepl".to_string()]),
+            auto_copy: Some("target".to_string()),
+            ..Default::default()
+        };
+        let mut options = WindowOptions {
+            target: Some("de".to_string()),
+            ..Default::default()
+        };
+        rule.apply(&mut options);
+        assert_eq!(options.target.as_deref(), Some("de"));
+        assert_eq!(options.services, Some(vec!["deepl".to_string()]));
+        assert_eq!(options.auto_copy.as_deref(), Some("target"));
+    }
+}
@@ -1,209 +1,1455 @@
+use crate::config::{get, reload_config_store, set, AppConfig, CONFIG_VERSION};
 use crate::error::Error;
+use crate::IDENTIFIER;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::{Datelike, NaiveDateTime, Utc};
 use dirs::config_dir;
-use log::info;
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use rand::RngCore;
 use reqwest_dav::{Auth, ClientBuilder, Depth};
-use std::io::Write;
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{Cursor, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tauri::api::notification::Notification;
 use walkdir::WalkDir;
 use zip::read::ZipArchive;
 use zip::write::SimpleFileOptions;
 
-#[tauri::command(async)]
-pub async fn webdav(
-    operate: &str,
-    url: String,
-    username: String,
-    password: String,
-    name: Option<String>,
-) -> Result<String, Error> {
-    // build a client
-    let client = ClientBuilder::new()
-        .set_host(url.clone())
-        .set_auth(Auth::Basic(username.clone(), password.clone()))
-        .build()?;
-    client.mkcol("/pot-app").await.unwrap_or_default();
-    let client = ClientBuilder::new()
-        .set_host(format!("{}/pot-app", url.trim_end_matches("/")))
-        .set_auth(Auth::Basic(username, password))
-        .build()?;
+// Where backups are kept, the archive is made and restored the same way for all of them
+trait BackupBackend {
+    async fn list(&self) -> Result<Vec<String>, Error>;
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), Error>;
+    async fn get(&self, name: &str) -> Result<Vec<u8>, Error>;
+    async fn delete(&self, name: &str) -> Result<(), Error>;
+}
+
+async fn run(backend: &impl BackupBackend, operate: &str, name: &str) -> Result<Value, Error> {
+    if operate != "list" {
+        check_name(name)?;
+    }
     match operate {
-        "list" => {
-            let res = client.list("/", Depth::Number(1)).await?;
-            let result = serde_json::to_string(&res)?;
-            Ok(result)
+        "list" => Ok(json!(backend.list().await?)),
+        "put" => {
+            // Errors can't be held across an await, the commands have to be Send
+            let data = encrypt_archive(create_archive()?)?;
+            backend.put(name, data).await?;
+            info!("Backup {} created", name);
+            Ok(Value::Null)
         }
         "get" => {
-            let res = client.get(&format!("/{}", name.unwrap())).await?;
-            let data = res.bytes().await?;
-            let mut config_dir_path = config_dir().unwrap();
-            config_dir_path = config_dir_path.join("com.pot-app.desktop");
-            let zip_path = config_dir_path.join("archive.zip");
-
-            let mut zip_file = std::fs::File::create(&zip_path)?;
-            zip_file.write_all(&data)?;
-            let mut zip_file = std::fs::File::open(&zip_path)?;
-            let mut zip = ZipArchive::new(&mut zip_file)?;
-            zip.extract(config_dir_path)?;
-            Ok("".to_string())
+            restore_archive(&decrypt_archive(backend.get(name).await?)?)?;
+            info!("Backup {} restored", name);
+            Ok(Value::Null)
         }
-        "put" => {
-            let mut config_dir_path = match config_dir() {
-                Some(v) => v,
-                None => {
-                    return Err(Error::Error("WebDav Get Config Dir Error".into()));
-                }
-            };
-            config_dir_path = config_dir_path.join("com.pot-app.desktop");
-            let zip_path = config_dir_path.join("archive.zip");
-            let config_path = config_dir_path.join("config.json");
-            let database_path = config_dir_path.join("history.db");
-            let plugin_path = config_dir_path.join("plugins");
-
-            let zip_file = std::fs::File::create(&zip_path)?;
-            let mut zip = zip::ZipWriter::new(zip_file);
-            let options =
-                SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
-            zip.start_file("config.json", options)?;
-            zip.write(&std::fs::read(&config_path)?)?;
-            if database_path.exists() {
-                zip.start_file("history.db", options)?;
-                zip.write(&std::fs::read(&database_path)?)?;
+        "delete" => {
+            backend.delete(name).await?;
+            Ok(Value::Null)
+        }
+        _ => Err(Error::Error(
+            format!("Backup Operate Error: {}", operate).into(),
+        )),
+    }
+}
+
+// Names end up in paths and urls
+fn check_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(Error::Error(
+            format!("Invalid Backup Name: {}", name).into(),
+        ));
+    }
+    Ok(())
+}
+
+fn app_config_dir() -> Result<PathBuf, Error> {
+    match config_dir() {
+        Some(v) => Ok(v.join(IDENTIFIER)),
+        None => Err(Error::Error("Get Config Dir Error".into())),
+    }
+}
+
+// Backups only carry references to the secrets, never the secrets themselves
+fn sealed_config(config_path: &std::path::Path) -> Result<Vec<u8>, Error> {
+    let config: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&std::fs::read(config_path)?)?;
+    let mut sealed = serde_json::Map::new();
+    for (key, value) in config {
+        let value = crate::secret::seal(&key, value)?;
+        sealed.insert(key, value);
+    }
+    Ok(serde_json::to_vec_pretty(&sealed)?)
+}
+
+// config.json, history.db and the plugins, with a manifest to check them on restore
+fn create_archive() -> Result<Vec<u8>, Error> {
+    let config_dir_path = app_config_dir()?;
+    let config_path = config_dir_path.join("config.json");
+    let database_path = config_dir_path.join("history.db");
+    let plugin_path = config_dir_path.join("plugins");
+
+    let mut files = vec![("config.json".to_string(), sealed_config(&config_path)?)];
+    if database_path.exists() {
+        checkpoint_history(&database_path)?;
+        files.push(("history.db".to_string(), std::fs::read(&database_path)?));
+    }
+    if plugin_path.exists() {
+        for entry in WalkDir::new(plugin_path) {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_file() {
+                let file_name = archive_name(path.strip_prefix(&config_dir_path)?)?;
+                info!("adding file {path:?} as {file_name:?} ...");
+                files.push((file_name, std::fs::read(path)?));
             }
-            if plugin_path.exists() {
-                for entry in WalkDir::new(plugin_path) {
-                    let entry = entry?;
-                    let path = entry.path();
-                    let file_name = match path.strip_prefix(&config_dir_path)?.to_str() {
-                        Some(v) => v,
-                        None => return Err(Error::Error("WebDav Strip Prefix Error".into())),
-                    };
-                    if path.is_file() {
-                        info!("adding file {path:?} as {file_name:?} ...");
-                        zip.start_file(file_name, options)?;
-                        zip.write(&std::fs::read(entry.path())?)?;
-                    } else {
-                        continue;
-                    }
-                }
+        }
+    }
+
+    let manifest = Manifest {
+        app_version: app_version(),
+        config_version: CONFIG_VERSION,
+        created: Utc::now().to_rfc3339(),
+        files: files
+            .iter()
+            .map(|(name, data)| (name.clone(), hex::encode(Sha256::digest(data))))
+            .collect(),
+    };
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, data) in files {
+        zip.start_file(name, options)?;
+        zip.write_all(&data)?;
+    }
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+// Recent history may only be in history.db-wal, write it back so the file alone is complete
+fn checkpoint_history(path: &Path) -> Result<(), Error> {
+    let conn = Connection::open(path)?;
+    let busy: i64 = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+    if busy != 0 {
+        warn!("History is in use, the backup may miss the latest entries");
+    }
+    Ok(())
+}
+
+// Always `/`, backups made on Windows are restored on the other systems too
+fn archive_name(path: &Path) -> Result<String, Error> {
+    let mut parts = vec![];
+    for component in path.components() {
+        match component.as_os_str().to_str() {
+            Some(v) => parts.push(v),
+            None => return Err(Error::Error("Strip Prefix Error".into())),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn app_version() -> String {
+    match crate::APP.get() {
+        Some(app) => app.package_info().version.to_string(),
+        None => String::new(),
+    }
+}
+
+// Encrypted archives are `POTBAK` and a version, the argon2id params and salt, the nonce
+// and the AES-256-GCM ciphertext of the zip, with the header as associated data
+const ARCHIVE_MAGIC: &[u8; 6] = b"POTBAK";
+const ARCHIVE_VERSION: u8 = 1;
+const HEADER_LENGTH: usize = 6 + 1 + 12 + 16 + 12;
+
+// The passphrase is optional, without it archives are plain zips
+fn backup_password() -> String {
+    match crate::config::get("backup_password") {
+        Some(serde_json::Value::String(v)) => v,
+        _ => String::new(),
+    }
+}
+
+fn derive_key(password: &str, salt: &[u8], params: Params) -> Result<[u8; 32], Error> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| Error::Error(format!("Derive Backup Key Error: {}", e).into()))?;
+    Ok(key)
+}
+
+fn encrypt_archive(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let password = backup_password();
+    if password.is_empty() {
+        return Ok(data);
+    }
+    let params = Params::default();
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut header = ARCHIVE_MAGIC.to_vec();
+    header.push(ARCHIVE_VERSION);
+    for cost in [params.m_cost(), params.t_cost(), params.p_cost()] {
+        header.extend(cost.to_le_bytes());
+    }
+    header.extend(salt);
+    header.extend(nonce);
+
+    let key = derive_key(&password, &salt, params)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let payload = Payload {
+        msg: &data,
+        aad: &header,
+    };
+    let encrypted = cipher
+        .encrypt(Nonce::from_slice(&nonce), payload)
+        .map_err(|_| Error::Error("Encrypt Backup Error".into()))?;
+    header.extend(encrypted);
+    Ok(header)
+}
+
+// Plain zips are passed through, so old backups can still be restored
+fn decrypt_archive(data: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if !data.starts_with(ARCHIVE_MAGIC) {
+        return Ok(data);
+    }
+    if data.len() < HEADER_LENGTH || data[6] != ARCHIVE_VERSION {
+        return Err(Error::Error("Unsupported Encrypted Backup".into()));
+    }
+    let password = backup_password();
+    if password.is_empty() {
+        return Err(Error::Error(
+            "Backup Is Encrypted, Please Set The Backup Password".into(),
+        ));
+    }
+    let (header, encrypted) = data.split_at(HEADER_LENGTH);
+    let params = archive_params(header)?;
+    let salt = &header[19..35];
+    let nonce = &header[35..47];
+
+    let key = derive_key(&password, salt, params)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let payload = Payload {
+        msg: encrypted,
+        aad: header,
+    };
+    // A wrong password and a damaged archive look the same to AES-GCM
+    cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| Error::Error("Wrong Backup Password Or Damaged Backup".into()))
+}
+
+// The header isn't trusted yet, it may not ask for more than `encrypt_archive` uses,
+// or a hostile archive could take gigabytes of memory for every attempt
+fn archive_params(header: &[u8]) -> Result<Params, Error> {
+    let cost = |i: usize| u32::from_le_bytes(header[7 + i * 4..11 + i * 4].try_into().unwrap());
+    let (m_cost, t_cost, p_cost) = (cost(0), cost(1), cost(2));
+    if m_cost > Params::DEFAULT_M_COST
+        || t_cost > Params::DEFAULT_T_COST
+        || p_cost > Params::DEFAULT_P_COST
+    {
+        return Err(Error::Error("Unsupported Encrypted Backup".into()));
+    }
+    Params::new(m_cost, t_cost, p_cost, Some(32))
+        .map_err(|_| Error::Error("Unsupported Encrypted Backup".into()))
+}
+
+const MANIFEST: &str = "manifest.json";
+const STAGING_DIR: &str = "restore_staging";
+const ROLLBACK_DIR: &str = "restore_rollback";
+// What a backup replaces, everything else in the config dir is left alone
+const RESTORED: [&str; 3] = ["config.json", "history.db", "plugins"];
+// SQLite files that belong to history.db and have to move with it
+const HISTORY_SIDECARS: [&str; 2] = ["history.db-wal", "history.db-shm"];
+
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Manifest {
+    app_version: String,
+    config_version: u64,
+    created: String,
+    // sha256 of each file
+    files: BTreeMap<String, String>,
+}
+
+// Nothing live is touched until the whole archive is extracted and checked
+fn restore_archive(data: &[u8]) -> Result<(), Error> {
+    let dir = app_config_dir()?;
+    let staging = dir.join(STAGING_DIR);
+    remove_path(&staging)?;
+    std::fs::create_dir_all(&staging)?;
+    let result = extract_archive(data, &staging)
+        .and_then(|_| verify_staging(&staging))
+        .and_then(|_| match AppConfig::load().backup_merge_history {
+            true => merge_history(&dir, &staging),
+            false => Ok(()),
+        })
+        .and_then(|_| swap_in(&dir, &staging));
+    let _ = remove_path(&staging);
+    result?;
+    reload_config_store();
+    Ok(())
+}
+
+fn extract_archive(data: &[u8], staging: &Path) -> Result<(), Error> {
+    let mut zip = ZipArchive::new(Cursor::new(data))?;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        // `../../.bashrc` or `/etc/passwd` would end up outside the staging dir
+        let path = match file.enclosed_name() {
+            Some(path) if is_restorable(&path) => path,
+            _ => {
+                return Err(Error::Error(
+                    format!("Invalid Backup Entry: {}", file.name()).into(),
+                ))
             }
+        };
+        let target = staging.join(path);
+        if file.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::io::copy(&mut file, &mut std::fs::File::create(&target)?)?;
+    }
+    Ok(())
+}
 
-            zip.finish()?;
-            match client
-                .put(&format!("/{}", name.unwrap()), std::fs::read(&zip_path)?)
-                .await
-            {
-                Ok(()) => return Ok("".to_string()),
-                Err(e) => {
-                    return Err(Error::Error(format!("WebDav Put Error: {}", e).into()));
-                }
+fn is_restorable(path: &Path) -> bool {
+    let mut components = path.components();
+    let first = match components.next() {
+        Some(Component::Normal(first)) => first.to_str(),
+        _ => None,
+    };
+    match first {
+        Some("plugins") => true,
+        Some(name) => (name == MANIFEST || RESTORED.contains(&name)) && components.next().is_none(),
+        None => false,
+    }
+}
+
+// Backups from before the manifest only get their content checked
+fn verify_staging(staging: &Path) -> Result<(), Error> {
+    let manifest_path = staging.join(MANIFEST);
+    if manifest_path.exists() {
+        let manifest: Manifest = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+        std::fs::remove_file(&manifest_path)?;
+        info!(
+            "Restore backup of pot {} from {}",
+            manifest.app_version, manifest.created
+        );
+        if manifest.config_version > CONFIG_VERSION {
+            return Err(Error::Error(
+                format!(
+                    "Backup Is From A Newer Version Of Pot: {}",
+                    manifest.app_version
+                )
+                .into(),
+            ));
+        }
+        let mut files = BTreeSet::new();
+        for entry in WalkDir::new(staging) {
+            let entry = entry?;
+            if entry.path().is_file() {
+                files.insert(archive_name(entry.path().strip_prefix(staging)?)?);
             }
         }
+        if !files.iter().eq(manifest.files.keys()) {
+            return Err(Error::Error("Backup Files Don't Match The Manifest".into()));
+        }
+        for (name, checksum) in &manifest.files {
+            let data = std::fs::read(staging.join(name))?;
+            if hex::encode(Sha256::digest(&data)) != *checksum {
+                return Err(Error::Error(
+                    format!("Backup Checksum Mismatch: {}", name).into(),
+                ));
+            }
+        }
+    } else {
+        warn!("Backup has no manifest, only checking its content");
+    }
+    let config = std::fs::read(staging.join("config.json"))
+        .map_err(|_| Error::Error("Backup Has No config.json".into()))?;
+    serde_json::from_slice::<Map<String, Value>>(&config)
+        .map_err(|e| Error::Error(format!("Invalid config.json In Backup: {}", e).into()))?;
+    let database = staging.join("history.db");
+    if database.exists() && !std::fs::read(&database)?.starts_with(b"SQLite format 3\0") {
+        return Err(Error::Error("Backup history.db Is Not A Database".into()));
+    }
+    Ok(())
+}
+
+// Same table the translate window creates
+const HISTORY_TABLE: &str = "CREATE TABLE history(id INTEGER PRIMARY KEY AUTOINCREMENT, text TEXT NOT NULL,source TEXT NOT NULL,target TEXT NOT NULL,service TEXT NOT NULL, result TEXT NOT NULL,timestamp INTEGER NOT NULL)";
+
+// Replace the staged history.db with local and backup history combined, so it's swapped in
+// and undone like any other restore. Ids are renumbered by time, the local copy of a duplicate wins
+fn merge_history(dir: &Path, staging: &Path) -> Result<(), Error> {
+    let local = dir.join("history.db");
+    let backup = staging.join("history.db");
+    if !local.exists() || !backup.exists() {
+        return Ok(());
+    }
+    let merged = staging.join("history.merged");
+    remove_path(&merged)?;
+    let conn = Connection::open(&merged)?;
+    conn.execute_batch(HISTORY_TABLE)?;
+    let mut sources = vec![];
+    for (origin, (alias, path)) in [("local", &local), ("backup", &backup)]
+        .into_iter()
+        .enumerate()
+    {
+        conn.execute(
+            &format!("ATTACH DATABASE ?1 AS {alias}"),
+            [path.to_string_lossy().to_string()],
+        )?;
+        let has_history: bool = conn.query_row(
+            &format!(
+                "SELECT EXISTS(SELECT 1 FROM {alias}.sqlite_master WHERE type = 'table' AND name = 'history')"
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        if has_history {
+            sources.push(format!(
+                "SELECT text, source, target, service, result, timestamp, {origin} AS origin FROM {alias}.history"
+            ));
+        }
+    }
+    if !sources.is_empty() {
+        // With a single min() SQLite takes the other columns from that row
+        let added = conn.execute(
+            &format!(
+                "INSERT INTO history (text, source, target, service, result, timestamp) \
+                 SELECT text, source, target, service, result, timestamp FROM ( \
+                 SELECT text, source, target, service, result, timestamp, MIN(origin) FROM ({}) \
+                 GROUP BY text, source, target, service, timestamp) ORDER BY timestamp",
+                sources.join(" UNION ALL ")
+            ),
+            [],
+        )?;
+        info!("Merged history has {} entries", added);
+    }
+    drop(conn);
+    std::fs::rename(&merged, &backup)?;
+    Ok(())
+}
+
+// Each rename is atomic, the replaced files are kept in the rollback dir until the next restore
+fn swap_in(dir: &Path, staging: &Path) -> Result<(), Error> {
+    let rollback = dir.join(ROLLBACK_DIR);
+    remove_path(&rollback)?;
+    std::fs::create_dir_all(&rollback)?;
+    let mut names: Vec<&str> = RESTORED
+        .into_iter()
+        .filter(|name| staging.join(name).exists())
+        .collect();
+    if names.contains(&"history.db") {
+        names.extend(HISTORY_SIDECARS);
+    }
+    let mut moved = vec![];
+    let mut placed = vec![];
+    let mut result = move_items(dir, &rollback, &names, &mut moved);
+    if result.is_ok() {
+        result = move_items(staging, dir, &names, &mut placed);
+    }
+    if let Err(e) = result {
+        warn!("Restore failed, putting the previous files back: {}", e);
+        for name in placed {
+            let _ = remove_path(&dir.join(name));
+        }
+        for name in moved {
+            let _ = std::fs::rename(rollback.join(name), dir.join(name));
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn move_items<'a>(
+    from: &Path,
+    to: &Path,
+    names: &[&'a str],
+    moved: &mut Vec<&'a str>,
+) -> Result<(), Error> {
+    for name in names {
+        if from.join(name).exists() {
+            std::fs::rename(from.join(name), to.join(name))?;
+            moved.push(*name);
+        }
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> Result<(), Error> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+// Swap the files of the last restore back in, doing it again brings the restored ones back
+fn undo_last_restore() -> Result<(), Error> {
+    let dir = app_config_dir()?;
+    let rollback = dir.join(ROLLBACK_DIR);
+    if !rollback.is_dir() || std::fs::read_dir(&rollback)?.next().is_none() {
+        return Err(Error::Error("No Restore To Undo".into()));
+    }
+    let staging = dir.join(STAGING_DIR);
+    remove_path(&staging)?;
+    std::fs::rename(&rollback, &staging)?;
+    let result = swap_in(&dir, &staging);
+    let _ = remove_path(&staging);
+    result?;
+    reload_config_store();
+    info!("Undid last restore");
+    Ok(())
+}
+
+struct WebDav {
+    client: reqwest_dav::Client,
+}
+
+impl WebDav {
+    async fn new(url: &str, username: &str, password: &str) -> Result<Self, Error> {
+        let client = ClientBuilder::new()
+            .set_host(url.to_string())
+            .set_auth(Auth::Basic(username.to_string(), password.to_string()))
+            .build()?;
+        client.mkcol("/pot-app").await.unwrap_or_default();
+        let client = ClientBuilder::new()
+            .set_host(format!("{}/pot-app", url.trim_end_matches("/")))
+            .set_auth(Auth::Basic(username.to_string(), password.to_string()))
+            .build()?;
+        Ok(Self { client })
+    }
+}
+
+impl BackupBackend for WebDav {
+    async fn list(&self) -> Result<Vec<String>, Error> {
+        let entries = serde_json::to_value(self.client.list("/", Depth::Number(1)).await?)?;
+        let names = entries
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry["File"]["href"].as_str())
+            .filter_map(|href| href.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect();
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        match self.client.put(&format!("/{}", name), data).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::Error(format!("WebDav Put Error: {}", e).into())),
+        }
+    }
+
+    async fn get(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let res = self.client.get(&format!("/{}", name)).await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), Error> {
+        match self.client.delete(&format!("/{}", name)).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::Error(format!("WebDav Delete Error: {}", e).into())),
+        }
+    }
+}
+
+struct Local {
+    dir: PathBuf,
+}
 
-        "delete" => match client.delete(&format!("/{}", name.unwrap())).await {
-            Ok(()) => return Ok("".to_string()),
-            Err(e) => {
-                return Err(Error::Error(format!("WebDav Delete Error: {}", e).into()));
+impl BackupBackend for Local {
+    async fn list(&self) -> Result<Vec<String>, Error> {
+        let mut names = vec![];
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_file() && name.ends_with(".zip") {
+                names.push(name);
             }
-        },
-        _ => {
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        Ok(std::fs::write(self.dir.join(name), data)?)
+    }
+
+    async fn get(&self, name: &str) -> Result<Vec<u8>, Error> {
+        Ok(std::fs::read(self.dir.join(name))?)
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), Error> {
+        Ok(std::fs::remove_file(self.dir.join(name))?)
+    }
+}
+
+const ALIYUN_API: &str = "https://openapi.alipan.com/adrive/v1.0";
+
+// Aliyun Drive through its open api, the token comes from the qr code login of the config window
+struct Aliyun {
+    client: reqwest::Client,
+    token: String,
+    drive_id: String,
+    dir_id: String,
+}
+
+impl Aliyun {
+    async fn new(token: &str) -> Result<Self, Error> {
+        if token.is_empty() {
+            return Err(Error::Error("Please Login To Aliyun Drive First".into()));
+        }
+        let mut backend = Self {
+            client: reqwest::Client::new(),
+            token: token.to_string(),
+            drive_id: String::new(),
+            dir_id: String::new(),
+        };
+        let drive = backend.call("user/getDriveInfo", json!({})).await?;
+        backend.drive_id = aliyun_field(&drive, "default_drive_id")?;
+        // `refuse` hands back the folder when it already exists
+        let dir = backend
+            .call(
+                "openFile/create",
+                json!({
+                    "drive_id": backend.drive_id,
+                    "parent_file_id": "root",
+                    "name": "pot-app",
+                    "type": "folder",
+                    "check_name_mode": "refuse",
+                }),
+            )
+            .await?;
+        backend.dir_id = aliyun_field(&dir, "file_id")?;
+        Ok(backend)
+    }
+
+    async fn call(&self, path: &str, body: Value) -> Result<Value, Error> {
+        let res = self
+            .client
+            .post(format!("{ALIYUN_API}/{path}"))
+            .bearer_auth(&self.token)
+            .json(&body)
+            .send()
+            .await?;
+        let status = res.status();
+        let result: Value = res.json().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(Error::Error(
+                format!(
+                    "Aliyun Drive Error {}: {}",
+                    status,
+                    result["message"].as_str().unwrap_or_default()
+                )
+                .into(),
+            ));
+        }
+        Ok(result)
+    }
+
+    async fn file_id(&self, name: &str) -> Result<String, Error> {
+        let file = self
+            .call(
+                "openFile/get_by_path",
+                json!({
+                    "drive_id": self.drive_id,
+                    "file_path": format!("/pot-app/{name}"),
+                }),
+            )
+            .await?;
+        aliyun_field(&file, "file_id")
+    }
+}
+
+fn aliyun_field(result: &Value, key: &str) -> Result<String, Error> {
+    match result[key].as_str() {
+        Some(v) => Ok(v.to_string()),
+        None => Err(Error::Error(
+            format!("Can Not Find {} In {}", key, result).into(),
+        )),
+    }
+}
+
+impl BackupBackend for Aliyun {
+    async fn list(&self) -> Result<Vec<String>, Error> {
+        let mut names = vec![];
+        let mut marker = String::new();
+        loop {
+            let mut body = json!({
+                "drive_id": self.drive_id,
+                "parent_file_id": self.dir_id,
+                "type": "file",
+                "order_by": "name",
+                "limit": 100,
+            });
+            if !marker.is_empty() {
+                body["marker"] = json!(marker);
+            }
+            let result = self.call("openFile/list", body).await?;
+            let Some(items) = result["items"].as_array() else {
+                return Err(Error::Error(
+                    format!("Get File List Error: {}", result).into(),
+                ));
+            };
+            names.extend(
+                items
+                    .iter()
+                    .filter_map(|item| item["name"].as_str().map(String::from)),
+            );
+            match result["next_marker"].as_str() {
+                Some(next) if !next.is_empty() => marker = next.to_string(),
+                _ => break,
+            }
+        }
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        let file = self
+            .call(
+                "openFile/create",
+                json!({
+                    "drive_id": self.drive_id,
+                    "parent_file_id": self.dir_id,
+                    "name": name,
+                    "type": "file",
+                    "check_name_mode": "refuse",
+                }),
+            )
+            .await?;
+        let Some(upload_url) = file["part_info_list"][0]["upload_url"].as_str() else {
+            return Err(Error::Error(
+                format!("Can Not Find upload_url In {}", file).into(),
+            ));
+        };
+        self.client
+            .put(upload_url)
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?;
+        let complete = json!({
+            "drive_id": self.drive_id,
+            "file_id": aliyun_field(&file, "file_id")?,
+            "upload_id": aliyun_field(&file, "upload_id")?,
+        });
+        self.call("openFile/complete", complete).await?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let file_id = self.file_id(name).await?;
+        let download = self
+            .call(
+                "openFile/getDownloadUrl",
+                json!({ "drive_id": self.drive_id, "file_id": file_id }),
+            )
+            .await?;
+        let url = aliyun_field(&download, "url")?;
+        let res = self.client.get(url).send().await?.error_for_status()?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), Error> {
+        let file_id = self.file_id(name).await?;
+        self.call(
+            "openFile/delete",
+            json!({ "drive_id": self.drive_id, "file_id": file_id }),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+// S3 compatible storage like MinIO or Garage, with path-style urls and signature v4
+struct S3 {
+    endpoint: url::Url,
+    region: String,
+    bucket: String,
+    access_key: String,
+    secret_key: String,
+}
+
+const S3_PREFIX: &str = "pot-app/";
+
+impl S3 {
+    fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<Self, Error> {
+        let endpoint = url::Url::parse(endpoint)
+            .map_err(|e| Error::Error(format!("Invalid S3 Endpoint: {}", e).into()))?;
+        if bucket.is_empty() {
+            return Err(Error::Error("S3 Bucket Is Required".into()));
+        }
+        Ok(Self {
+            endpoint,
+            // MinIO and Garage take any region, `us-east-1` is the usual default
+            region: match region.trim() {
+                "" => "us-east-1".to_string(),
+                v => v.to_string(),
+            },
+            bucket: bucket.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        })
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        payload: Vec<u8>,
+    ) -> Result<reqwest::Response, Error> {
+        let path = match key {
+            Some(key) => format!(
+                "/{}/{}",
+                uri_encode(&self.bucket, true),
+                uri_encode(key, false)
+            ),
+            None => format!("/{}", uri_encode(&self.bucket, true)),
+        };
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&payload));
+        let canonical_request = canonical_request(
+            method.as_str(),
+            &path,
+            &query,
+            &host,
+            &payload_hash,
+            &amz_date,
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let signature = signature(
+            &signing_key(&self.secret_key, &date, &self.region, "s3"),
+            &amz_date,
+            &scope,
+            &canonical_request,
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query(if query.is_empty() { None } else { Some(&query) });
+        let res = reqwest::Client::new()
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header("authorization", authorization)
+            .body(payload)
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
             return Err(Error::Error(
-                format!("WebDav Operate Error: {}", operate).into(),
+                format!(
+                    "S3 Error {}: {}",
+                    status,
+                    xml_values(&body, "Message").join(" ")
+                )
+                .into(),
             ));
         }
+        Ok(res)
     }
 }
 
-#[tauri::command(async)]
-pub async fn local(operate: &str, path: String) -> Result<String, Error> {
-    match operate {
-        "put" => {
-            let mut config_dir_path = match config_dir() {
-                Some(v) => v,
-                None => {
-                    return Err(Error::Error("WebDav Get Config Dir Error".into()));
+impl BackupBackend for S3 {
+    async fn list(&self) -> Result<Vec<String>, Error> {
+        let mut names = vec![];
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", S3_PREFIX)];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let res = self
+                .send(reqwest::Method::GET, None, &query, vec![])
+                .await?;
+            let body = res.text().await?;
+            names.extend(
+                xml_values(&body, "Key")
+                    .into_iter()
+                    .filter_map(|key| key.strip_prefix(S3_PREFIX).map(String::from))
+                    .filter(|name| !name.is_empty() && !name.contains('/')),
+            );
+            // Listings stop at 1000 keys
+            match xml_values(&body, "NextContinuationToken").pop() {
+                Some(next)
+                    if xml_values(&body, "IsTruncated").first().map(String::as_str)
+                        == Some("true") =>
+                {
+                    token = Some(next)
                 }
+                _ => break,
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    async fn put(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        let key = format!("{S3_PREFIX}{name}");
+        self.send(reqwest::Method::PUT, Some(&key), &[], data)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let key = format!("{S3_PREFIX}{name}");
+        let res = self
+            .send(reqwest::Method::GET, Some(&key), &[], vec![])
+            .await?;
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), Error> {
+        let key = format!("{S3_PREFIX}{name}");
+        self.send(reqwest::Method::DELETE, Some(&key), &[], vec![])
+            .await?;
+        Ok(())
+    }
+}
+
+fn canonical_request(
+    method: &str,
+    path: &str,
+    query: &str,
+    host: &str,
+    payload_hash: &str,
+    amz_date: &str,
+) -> String {
+    format!(
+        "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method, path, query, host, payload_hash, amz_date, payload_hash
+    )
+}
+
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let mut key = format!("AWS4{}", secret_key).into_bytes();
+    for part in [date, region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    key
+}
+
+fn signature(signing_key: &[u8], amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    hex::encode(hmac_sha256(signing_key, string_to_sign.as_bytes()))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Percent-encoding as signature v4 wants it, `/` is kept in object keys
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// S3 answers are flat enough to pick the values out of the tags
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    xml.split(&open)
+        .skip(1)
+        .filter_map(|v| v.split(&close).next())
+        .map(|v| {
+            v.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
+
+// Automatic backups are checked for every 10 minutes, a failed one is tried again after an hour
+const SCHEDULE_CHECK: Duration = Duration::from_secs(10 * 60);
+const RETRY_AFTER: i64 = 60 * 60;
+const AUTO_PREFIX: &str = "pot-";
+const AUTO_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+pub fn start_backup_scheduler() {
+    std::thread::spawn(|| {
+        let mut last_failure = 0;
+        loop {
+            std::thread::sleep(SCHEDULE_CHECK);
+            let config = AppConfig::load();
+            let interval = match config.backup_schedule.as_str() {
+                "daily" => 24 * 60 * 60,
+                "weekly" => 7 * 24 * 60 * 60,
+                _ => continue,
             };
-            config_dir_path = config_dir_path.join("com.pot-app.desktop");
-            let config_path = config_dir_path.join("config.json");
-            let database_path = config_dir_path.join("history.db");
-            let plugin_path = config_dir_path.join("plugins");
-
-            let zip_file = std::fs::File::create(&path)?;
-            let mut zip = zip::ZipWriter::new(zip_file);
-            let options =
-                SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
-            zip.start_file("config.json", options)?;
-            zip.write(&std::fs::read(&config_path)?)?;
-            if database_path.exists() {
-                zip.start_file("history.db", options)?;
-                zip.write(&std::fs::read(&database_path)?)?;
+            let now = Utc::now().timestamp();
+            if now - config.backup_last_run < interval || now - last_failure < RETRY_AFTER {
+                continue;
             }
-            if plugin_path.exists() {
-                for entry in WalkDir::new(plugin_path) {
-                    let entry = entry?;
-                    let path = entry.path();
-                    let file_name = match path.strip_prefix(&config_dir_path)?.to_str() {
-                        Some(v) => v,
-                        None => return Err(Error::Error("Strip Prefix Error".into())),
-                    };
-                    if path.is_file() {
-                        info!("adding file {path:?} as {file_name:?} ...");
-                        zip.start_file(file_name, options)?;
-                        zip.write(&std::fs::read(entry.path())?)?;
-                    } else {
-                        continue;
-                    }
+            match tauri::async_runtime::block_on(scheduled_backup(&config)) {
+                Ok(name) => {
+                    info!("Automatic backup {} created", name);
+                    set("backup_last_run", now);
+                }
+                Err(e) => {
+                    last_failure = now;
+                    error!("Automatic backup failed: {}", e);
+                    let app_handle = crate::APP.get().unwrap();
+                    let _ = Notification::new(app_handle.config().tauri.bundle.identifier.clone())
+                        .title("Automatic backup failed")
+                        .body(e.to_string())
+                        .icon("pot")
+                        .show();
                 }
             }
-
-            zip.finish()?;
-            Ok("".to_string())
         }
-        "get" => {
-            let mut config_dir_path = config_dir().unwrap();
-            config_dir_path = config_dir_path.join("com.pot-app.desktop");
+    });
+}
+
+fn config_string(key: &str) -> String {
+    match get(key) {
+        Some(Value::String(v)) => v,
+        _ => String::new(),
+    }
+}
+
+// Archives are named after the machine, so each one only prunes its own
+fn host_name() -> String {
+    let name = hostname::get()
+        .map(|v| v.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.is_empty() {
+        true => "unknown".to_string(),
+        false => name,
+    }
+}
 
-            let mut zip_file = std::fs::File::open(&path)?;
-            let mut zip = ZipArchive::new(&mut zip_file)?;
-            zip.extract(config_dir_path)?;
-            Ok("".to_string())
+async fn scheduled_backup(config: &AppConfig) -> Result<String, Error> {
+    let host = host_name();
+    let name = format!(
+        "{AUTO_PREFIX}{host}-{}.zip",
+        chrono::Local::now().format(AUTO_TIME_FORMAT)
+    );
+    match config.backup_type.as_str() {
+        "webdav" => {
+            let backend = WebDav::new(
+                &config_string("webdav_url"),
+                &config_string("webdav_username"),
+                &config_string("webdav_password"),
+            )
+            .await?;
+            backup_and_prune(&backend, &name, &host, config).await?
+        }
+        "s3" => {
+            let backend = S3::new(
+                &config_string("s3_endpoint"),
+                &config_string("s3_region"),
+                &config_string("s3_bucket"),
+                &config_string("s3_access_key"),
+                &config_string("s3_secret_key"),
+            )?;
+            backup_and_prune(&backend, &name, &host, config).await?
+        }
+        "local" => {
+            if config.backup_local_dir.is_empty() {
+                return Err(Error::Error(
+                    "Please Choose A Folder For Automatic Local Backups".into(),
+                ));
+            }
+            let backend = Local {
+                dir: PathBuf::from(&config.backup_local_dir),
+            };
+            std::fs::create_dir_all(&backend.dir)?;
+            backup_and_prune(&backend, &name, &host, config).await?
+        }
+        "aliyun" => {
+            let backend = Aliyun::new(&config_string("aliyun_access_token")).await?;
+            backup_and_prune(&backend, &name, &host, config).await?
         }
         _ => {
             return Err(Error::Error(
-                format!("Local Operate Error: {}", operate).into(),
-            ));
+                format!("Unknown Backup Type: {}", config.backup_type).into(),
+            ))
         }
     }
+    Ok(name)
 }
 
-#[tauri::command(async)]
-pub async fn aliyun(operate: &str, path: String, url: String) -> Result<String, Error> {
-    match operate {
-        "put" => {
-            let _ = reqwest::Client::new()
-                .put(&url)
-                .body(std::fs::read(&path)?)
-                .send()
-                .await?;
-            Ok("".to_string())
+async fn backup_and_prune(
+    backend: &impl BackupBackend,
+    name: &str,
+    host: &str,
+    config: &AppConfig,
+) -> Result<(), Error> {
+    run(backend, "put", name).await?;
+    // Manual backups and the ones of other machines are never pruned
+    let prefix = format!("{AUTO_PREFIX}{host}-");
+    let backups: Vec<(NaiveDateTime, String)> = backend
+        .list()
+        .await?
+        .into_iter()
+        .filter_map(|name| {
+            let time = name.strip_prefix(&prefix)?.strip_suffix(".zip")?;
+            let time = NaiveDateTime::parse_from_str(time, AUTO_TIME_FORMAT).ok()?;
+            Some((time, name))
+        })
+        .collect();
+    let keep = retained(
+        backups.clone(),
+        config.backup_keep_last,
+        config.backup_keep_daily,
+        config.backup_keep_weekly,
+    );
+    for (_, name) in backups {
+        if keep.contains(&name) {
+            continue;
         }
-        "get" => {
-            let res = reqwest::Client::new().get(&url).send().await?;
-            let data = res.bytes().await?;
-            let mut config_dir_path = config_dir().unwrap();
-            config_dir_path = config_dir_path.join("com.pot-app.desktop");
-            let zip_path = config_dir_path.join("archive.zip");
-
-            let mut zip_file = std::fs::File::create(&zip_path)?;
-            zip_file.write_all(&data)?;
-            let mut zip_file = std::fs::File::open(&zip_path)?;
-            let mut zip = ZipArchive::new(&mut zip_file)?;
-            zip.extract(config_dir_path)?;
-            Ok("".to_string())
+        match backend.delete(&name).await {
+            Ok(()) => info!("Pruned backup {}", name),
+            Err(e) => warn!("Failed to prune backup {}: {}", name, e),
         }
-        _ => {
-            return Err(Error::Error(
-                format!("Local Operate Error: {}", operate).into(),
-            ));
+    }
+    Ok(())
+}
+
+// The newest `last` backups, and the newest one of each of the newest `daily` days and `weekly` weeks
+fn retained(
+    mut backups: Vec<(NaiveDateTime, String)>,
+    last: u32,
+    daily: u32,
+    weekly: u32,
+) -> HashSet<String> {
+    backups.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    let mut keep: HashSet<String> = backups
+        .iter()
+        .take(last as usize)
+        .map(|(_, name)| name.clone())
+        .collect();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (time, name) in &backups {
+        let day = time.date();
+        if days.len() < daily as usize && days.insert(day) {
+            keep.insert(name.clone());
+        }
+        let week = (time.iso_week().year(), time.iso_week().week());
+        if weeks.len() < weekly as usize && weeks.insert(week) {
+            keep.insert(name.clone());
+        }
+    }
+    // Never delete everything, whatever the policy says
+    if let Some((_, newest)) = backups.first() {
+        keep.insert(newest.clone());
+    }
+    keep
+}
+
+#[tauri::command(async)]
+pub async fn webdav(
+    operate: &str,
+    url: String,
+    username: String,
+    password: String,
+    name: Option<String>,
+) -> Result<Value, Error> {
+    let backend = WebDav::new(&url, &username, &password).await?;
+    run(&backend, operate, &name.unwrap_or_default()).await
+}
+
+// `path` is the archive, or the dir for `list`
+#[tauri::command(async)]
+pub async fn local(operate: &str, path: String) -> Result<Value, Error> {
+    let path = PathBuf::from(path);
+    if operate == "list" {
+        return run(&Local { dir: path }, operate, "").await;
+    }
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(Error::Error("Invalid Backup Path".into()));
+    };
+    let backend = Local {
+        dir: dir.to_path_buf(),
+    };
+    run(&backend, operate, &name.to_string_lossy()).await
+}
+
+#[tauri::command(async)]
+pub async fn aliyun(operate: &str, token: String, name: Option<String>) -> Result<Value, Error> {
+    let backend = Aliyun::new(&token).await?;
+    run(&backend, operate, &name.unwrap_or_default()).await
+}
+
+#[tauri::command(async)]
+pub async fn s3(
+    operate: &str,
+    endpoint: String,
+    region: String,
+    bucket: String,
+    access_key: String,
+    secret_key: String,
+    name: Option<String>,
+) -> Result<Value, Error> {
+    let backend = S3::new(&endpoint, &region, &bucket, &access_key, &secret_key)?;
+    run(&backend, operate, &name.unwrap_or_default()).await
+}
+
+#[tauri::command(async)]
+pub async fn undo_restore() -> Result<(), Error> {
+    undo_last_restore()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Examples from the signature v4 documentation of AWS
+    const EXAMPLE_SECRET: &str = "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY";
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn signature_matches_aws_list_objects_example() {
+        let amz_date = "20130524T000000Z";
+        let request = canonical_request(
+            "GET",
+            "/",
+            "max-keys=2&prefix=J",
+            "examplebucket.s3.amazonaws.com",
+            EMPTY_HASH,
+            amz_date,
+        );
+        assert_eq!(hex::encode(Sha256::digest(b"")), EMPTY_HASH);
+        assert_eq!(
+            signature(
+                &signing_key(EXAMPLE_SECRET, "20130524", "us-east-1", "s3"),
+                amz_date,
+                "20130524/us-east-1/s3/aws4_request",
+                &request,
+            ),
+            "34b48302e7b5fa45bde8084f4b7868a86f0a534bc59db6670ed5711ef69dc6f7"
+        );
+    }
+
+    fn header(m_cost: u32, t_cost: u32, p_cost: u32) -> Vec<u8> {
+        let mut header = ARCHIVE_MAGIC.to_vec();
+        header.push(ARCHIVE_VERSION);
+        for cost in [m_cost, t_cost, p_cost] {
+            header.extend(cost.to_le_bytes());
+        }
+        header.resize(HEADER_LENGTH, 0);
+        header
+    }
+
+    #[test]
+    fn archive_params_accepts_the_written_params() {
+        let params = Params::default();
+        let read =
+            archive_params(&header(params.m_cost(), params.t_cost(), params.p_cost())).unwrap();
+        assert_eq!(
+            (read.m_cost(), read.t_cost(), read.p_cost()),
+            (params.m_cost(), params.t_cost(), params.p_cost())
+        );
+    }
+
+    #[test]
+    fn archive_params_rejects_inflated_costs() {
+        let params = Params::default();
+        for (m_cost, t_cost, p_cost) in [
+            (1024 * 1024, params.t_cost(), params.p_cost()),
+            (params.m_cost(), 64, params.p_cost()),
+            (params.m_cost(), params.t_cost(), 16),
+            (u32::MAX, u32::MAX, u32::MAX),
+        ] {
+            assert!(archive_params(&header(m_cost, t_cost, p_cost)).is_err());
         }
+        // Below the minimum of argon2
+        assert!(archive_params(&header(0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn uri_encode_keeps_unreserved_characters() {
+        assert_eq!(
+            uri_encode("pot-app/a b~_.zip", false),
+            "pot-app/a%20b~_.zip"
+        );
+        assert_eq!(uri_encode("pot-app/a+b", true), "pot-app%2Fa%2Bb");
+        assert_eq!(uri_encode("备份", true), "%E5%A4%87%E4%BB%BD");
+    }
+
+    #[test]
+    fn xml_values_unescapes_entities() {
+        let xml = "<R><Key>pot-app/a&amp;b.zip</Key><Key>pot-app/c.zip</Key></R>";
+        assert_eq!(
+            xml_values(xml, "Key"),
+            vec!["pot-app/a&b.zip".to_string(), "pot-app/c.zip".to_string()]
+        );
+        assert!(xml_values(xml, "Message").is_empty());
+    }
+
+    fn backups(times: &[&str]) -> Vec<(NaiveDateTime, String)> {
+        times
+            .iter()
+            .map(|time| {
+                (
+                    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap(),
+                    time.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn kept(
+        backups: Vec<(NaiveDateTime, String)>,
+        last: u32,
+        daily: u32,
+        weekly: u32,
+    ) -> Vec<String> {
+        let mut kept: Vec<String> = retained(backups, last, daily, weekly).into_iter().collect();
+        kept.sort();
+        kept
+    }
+
+    #[test]
+    fn retained_keeps_the_newest_backups() {
+        let list = backups(&[
+            "2024-03-01 10:00",
+            "2024-03-04 12:00",
+            "2024-03-01 18:00",
+            "2024-03-02 09:00",
+        ]);
+        assert_eq!(
+            kept(list, 2, 0, 0),
+            vec!["2024-03-02 09:00", "2024-03-04 12:00"]
+        );
+    }
+
+    #[test]
+    fn retained_keeps_the_newest_backup_of_each_day() {
+        let list = backups(&[
+            "2024-03-01 10:00",
+            "2024-03-01 18:00",
+            "2024-03-02 09:00",
+            "2024-03-02 21:00",
+            "2024-03-04 12:00",
+        ]);
+        assert_eq!(
+            kept(list.clone(), 0, 2, 0),
+            vec!["2024-03-02 21:00", "2024-03-04 12:00"]
+        );
+        assert_eq!(
+            kept(list, 0, 7, 0),
+            vec!["2024-03-01 18:00", "2024-03-02 21:00", "2024-03-04 12:00"]
+        );
+    }
+
+    #[test]
+    fn retained_keeps_the_newest_backup_of_each_iso_week() {
+        // 2024-03-04 is a monday, the sunday before still belongs to the previous week
+        let list = backups(&[
+            "2024-02-20 08:00",
+            "2024-03-02 09:00",
+            "2024-03-03 23:00",
+            "2024-03-04 00:30",
+        ]);
+        assert_eq!(
+            kept(list, 0, 0, 2),
+            vec!["2024-03-03 23:00", "2024-03-04 00:30"]
+        );
+    }
+
+    #[test]
+    fn retained_combines_the_rules() {
+        let list = backups(&[
+            "2024-02-20 08:00",
+            "2024-03-02 09:00",
+            "2024-03-03 22:00",
+            "2024-03-03 23:00",
+            "2024-03-04 00:30",
+        ]);
+        assert_eq!(
+            kept(list, 1, 2, 3),
+            vec!["2024-02-20 08:00", "2024-03-03 23:00", "2024-03-04 00:30",]
+        );
+    }
+
+    #[test]
+    fn retained_never_drops_the_newest_backup() {
+        let list = backups(&["2024-03-01 10:00", "2024-03-04 12:00"]);
+        assert_eq!(kept(list, 0, 0, 0), vec!["2024-03-04 12:00"]);
+        assert!(kept(vec![], 3, 7, 4).is_empty());
     }
 }
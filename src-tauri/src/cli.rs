@@ -0,0 +1,637 @@
+// `pot translate "text" --to de` and friends
+// Commands are sent to the running instance over the http server, without one they run here
+use crate::config::{mask_secrets, unmask_secrets, ConfigSource, ENV_OVERRIDES};
+use crate::deep_link;
+use crate::policy::{is_locked, locked_value};
+use crate::server::{api_recognize, api_translate};
+use crate::window::{
+    config_window, input_translate, ocr_recognize, ocr_translate, selection_translate,
+    text_translate,
+};
+use crate::IDENTIFIER;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const USAGE: &str = "Usage:
+    pot translate <text> [--from <lang>] [--to <lang>] [--service <a,b>] [--json]
+    pot ocr <image> [--service <name>] [--lang <lang>] [--translate] [--to <lang>] [--json]
+    pot config get <key>
+    pot config set <key> <json value>
+    pot config source <key>
+    pot show <config|translate|recognize>
+    pot [--translate [text]] [--selection] [--ocr] [--ocr-translate] [--config] [--set <key>=<value>]
+
+Text is read from stdin when it is omitted or `-`.";
+
+pub enum Command {
+    Translate {
+        text: String,
+        from: Option<String>,
+        to: Option<String>,
+        services: Option<Vec<String>>,
+        json: bool,
+    },
+    Ocr {
+        path: String,
+        service: Option<String>,
+        lang: Option<String>,
+        translate: bool,
+        to: Option<String>,
+        json: bool,
+    },
+    ConfigGet(String),
+    ConfigSet(String, Value),
+    ConfigSource(String),
+    Show(String),
+    Help,
+}
+
+// The command to run in this process, because no instance was running
+pub static LOCAL_COMMAND: OnceCell<Command> = OnceCell::new();
+
+// Returns None when the arguments are not a cli command
+pub fn parse(args: &[String]) -> Option<Result<Command, String>> {
+    let (name, rest) = args.split_first()?;
+    let command = match name.as_str() {
+        "translate" => parse_translate(rest),
+        "ocr" => parse_ocr(rest),
+        "config" => parse_config(rest),
+        "show" => match rest {
+            [target] if ["config", "translate", "recognize"].contains(&target.as_str()) => {
+                Ok(Command::Show(target.clone()))
+            }
+            _ => Err("Usage: pot show <config|translate|recognize>".to_string()),
+        },
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => return None,
+    };
+    Some(command)
+}
+
+fn parse_translate(args: &[String]) -> Result<Command, String> {
+    let (positional, mut options) = parse_options(args, &["from", "to", "service"], &["json"])?;
+    let mut text = positional.join(" ");
+    if text.is_empty() || text == "-" {
+        text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| e.to_string())?;
+    }
+    if text.trim().is_empty() {
+        return Err("Text is empty".to_string());
+    }
+    Ok(Command::Translate {
+        text,
+        from: options.remove("from"),
+        to: options.remove("to"),
+        services: options
+            .remove("service")
+            .map(|v| v.split(',').map(|s| s.trim().to_string()).collect()),
+        json: options.contains_key("json"),
+    })
+}
+
+fn parse_ocr(args: &[String]) -> Result<Command, String> {
+    let (positional, mut options) =
+        parse_options(args, &["service", "lang", "to"], &["translate", "json"])?;
+    let path = match positional.as_slice() {
+        [path] => path.clone(),
+        _ => return Err("Usage: pot ocr <image>".to_string()),
+    };
+    Ok(Command::Ocr {
+        path,
+        service: options.remove("service"),
+        lang: options.remove("lang"),
+        translate: options.contains_key("translate"),
+        to: options.remove("to"),
+        json: options.contains_key("json"),
+    })
+}
+
+fn parse_config(args: &[String]) -> Result<Command, String> {
+    match args {
+        [action, key] if action == "get" => Ok(Command::ConfigGet(key.clone())),
+        [action, key] if action == "source" => Ok(Command::ConfigSource(key.clone())),
+        [action, key, value] if action == "set" => {
+            // Plain words don't need to be quoted as json strings
+            let value = serde_json::from_str(value).unwrap_or(json!(value));
+            Ok(Command::ConfigSet(key.clone(), value))
+        }
+        _ => Err(
+            "Usage: pot config get <key> | pot config set <key> <value> | pot config source <key>"
+                .to_string(),
+        ),
+    }
+}
+
+// Split `--name value` options and `--name` switches from the positional arguments
+fn parse_options(
+    args: &[String],
+    names: &[&str],
+    switches: &[&str],
+) -> Result<(Vec<String>, HashMap<String, String>), String> {
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(name) if names.contains(&name) => match iter.next() {
+                Some(v) => {
+                    options.insert(name.to_string(), v.clone());
+                }
+                None => return Err(format!("Missing value for --{name}")),
+            },
+            Some(name) if switches.contains(&name) => {
+                options.insert(name.to_string(), String::new());
+            }
+            Some(name) if !name.is_empty() => return Err(format!("Unknown option: --{name}")),
+            _ => positional.push(arg.clone()),
+        }
+    }
+    Ok((positional, options))
+}
+
+pub fn usage() -> &'static str {
+    USAGE
+}
+
+// Run the command against the running instance
+// Returns the exit code, or None if it has to run in this process
+pub fn run(command: &Command) -> Option<i32> {
+    #[cfg(windows)]
+    unsafe {
+        // Release builds have no console, print to the one we were started from
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+    let result = match command {
+        Command::Help => {
+            println!("{USAGE}");
+            return Some(0);
+        }
+        Command::Translate {
+            text,
+            from,
+            to,
+            services,
+            json,
+        } => request(
+            reqwest::Method::POST,
+            "/api/translate",
+            json!({ "text": text, "source": from, "target": to, "services": services })
+                .to_string()
+                .into_bytes(),
+        )
+        .map(|v| v.map(|v| print_translate(&v, *json))),
+        Command::Ocr {
+            path,
+            service,
+            lang,
+            translate,
+            to,
+            json,
+        } => {
+            let image = match std::fs::read(path) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("Failed to read {path}: {e}");
+                    return Some(1);
+                }
+            };
+            // Only the path and query are used, the host is filled in by `request`
+            let mut url = url::Url::parse("http://localhost/api/ocr").unwrap();
+            {
+                let mut query = url.query_pairs_mut();
+                if let Some(v) = service {
+                    query.append_pair("service", v);
+                }
+                if let Some(v) = lang {
+                    query.append_pair("lang", v);
+                }
+                if let Some(v) = to {
+                    query.append_pair("target", v);
+                }
+                if *translate {
+                    query.append_pair("translate", "true");
+                }
+            }
+            request(
+                reqwest::Method::POST,
+                &url[url::Position::BeforePath..],
+                image,
+            )
+            .map(|v| v.map(|v| print_ocr(&v, *json)))
+        }
+        Command::ConfigGet(key) => request(
+            reqwest::Method::GET,
+            &format!("/api/config/{key}"),
+            Vec::new(),
+        )
+        .map(|v| v.map(|v| print_value(&v["value"]))),
+        Command::ConfigSet(key, value) => request(
+            reqwest::Method::PUT,
+            &format!("/api/config/{key}"),
+            value.to_string().into_bytes(),
+        )
+        .map(|v| v.map(|_| 0)),
+        Command::ConfigSource(key) => request(
+            reqwest::Method::GET,
+            &format!("/api/config/{key}"),
+            Vec::new(),
+        )
+        .map(|v| v.map(|v| print_value(&v["source"]))),
+        Command::Show(target) => {
+            let path = match target.as_str() {
+                "config" => "/config",
+                "translate" => "/input_translate",
+                _ => "/ocr_recognize",
+            };
+            request(reqwest::Method::GET, path, Vec::new()).map(|v| v.map(|_| 0))
+        }
+    };
+    match result {
+        Some(Ok(code)) => Some(code),
+        Some(Err(e)) => {
+            eprintln!("{e}");
+            Some(1)
+        }
+        // Config is just a file, no need to start the app for it
+        None => match command {
+            Command::ConfigGet(key) => Some(local_config_get(key)),
+            Command::ConfigSet(key, value) => Some(local_config_set(key, value)),
+            Command::ConfigSource(key) => Some(local_config_source(key)),
+            _ => None,
+        },
+    }
+}
+
+// Returns None if no instance is listening
+fn request(method: reqwest::Method, path: &str, body: Vec<u8>) -> Option<Result<Value, String>> {
+    let config = read_config();
+    let address = config
+        .get("server_address")
+        .and_then(|v| v.as_str())
+        .unwrap_or("127.0.0.1");
+    // Wildcard addresses are reachable through loopback
+    let host = match address {
+        "" | "0.0.0.0" | "::" => "127.0.0.1".to_string(),
+        v if v.contains(':') => format!("[{v}]"),
+        v => v.to_string(),
+    };
+    let port = config
+        .get("server_port")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(60828);
+    let url = format!("http://{host}:{port}{path}");
+    let mut builder = reqwest::Client::new().request(method, url).body(body);
+    if config.get("server_auth").and_then(|v| v.as_bool()) == Some(true) {
+        let token = config
+            .get("server_token")
+            .map(|v| crate::secret::open(v.clone()))
+            .unwrap_or_default();
+        builder = builder.bearer_auth(token.as_str().unwrap_or_default());
+    } else {
+        // Without a token only localhost is served, the cli is local whatever the server address is
+        builder = builder.header("Host", format!("localhost:{port}"));
+    }
+    if path.starts_with("/api/translate") || path.starts_with("/api/config") {
+        builder = builder.header("Content-Type", "application/json");
+    }
+    tauri::async_runtime::block_on(async move {
+        let res = match builder.send().await {
+            Ok(v) => v,
+            Err(e) if e.is_connect() => return None,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        let status = res.status();
+        let text = match res.text().await {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e.to_string())),
+        };
+        let value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        if status.is_success() {
+            Some(Ok(value))
+        } else {
+            match value.get("error").and_then(|v| v.as_str()) {
+                Some(e) => Some(Err(e.to_string())),
+                None => Some(Err(status.to_string())),
+            }
+        }
+    })
+}
+
+fn config_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join(IDENTIFIER).join("config.json"))
+}
+
+fn read_config() -> serde_json::Map<String, Value> {
+    config_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|v| serde_json::from_str(&v).ok())
+        .unwrap_or_default()
+}
+
+// `--set` only exists in the running instance, the environment is the same here
+fn local_config_get(key: &str) -> i32 {
+    let value = locked_value(key)
+        .or(ENV_OVERRIDES.get(key))
+        .cloned()
+        .or_else(|| read_config().remove(key));
+    match value {
+        Some(v) => print_value(&mask_secrets(key, &v)),
+        None => {
+            eprintln!("Config not found");
+            1
+        }
+    }
+}
+
+fn local_config_source(key: &str) -> i32 {
+    let source = if is_locked(key) {
+        ConfigSource::Policy
+    } else if ENV_OVERRIDES.contains_key(key) {
+        ConfigSource::Env
+    } else if read_config().contains_key(key) {
+        ConfigSource::Stored
+    } else {
+        ConfigSource::Default
+    };
+    print_value(&json!(source))
+}
+
+fn local_config_set(key: &str, value: &Value) -> i32 {
+    if is_locked(key) {
+        eprintln!("Config {key} is locked by policy");
+        return 1;
+    }
+    let Some(path) = config_path() else {
+        eprintln!("Get Config Dir Failed");
+        return 1;
+    };
+    let mut config = read_config();
+    let value = unmask_secrets(value.clone(), config.get(key));
+    let value = match crate::secret::seal(key, value) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Failed to store secrets: {e}");
+            return 1;
+        }
+    };
+    config.insert(key.to_string(), value);
+    let result = std::fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| std::fs::write(&path, serde_json::to_string_pretty(&config).unwrap()));
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("Failed to write {}: {e}", path.display());
+            1
+        }
+    }
+}
+
+// Translate and ocr need the daemon window, run them without showing anything and quit
+pub fn run_headless(app_handle: AppHandle) {
+    let (tx, rx) = channel();
+    app_handle.once_global("daemon_ready", move |_| {
+        let _ = tx.send(());
+    });
+    thread::spawn(move || {
+        if rx.recv_timeout(Duration::from_secs(30)).is_err() {
+            eprintln!("Daemon window is not ready");
+            app_handle.exit(1);
+        }
+        let code = match LOCAL_COMMAND.get() {
+            Some(Command::Translate {
+                text,
+                from,
+                to,
+                services,
+                json,
+            }) => match api_translate(text.clone(), from.clone(), to.clone(), services.clone()) {
+                Ok(v) => print_translate(&v, *json),
+                Err((_, e)) => {
+                    eprintln!("{e}");
+                    1
+                }
+            },
+            Some(Command::Ocr {
+                path,
+                service,
+                lang,
+                translate,
+                to,
+                json,
+            }) => match std::fs::read(path) {
+                Ok(image) => {
+                    let result = api_recognize(
+                        &image,
+                        service.clone(),
+                        lang.clone(),
+                        Some(*translate),
+                        to.clone(),
+                    );
+                    match result {
+                        Ok(v) => print_ocr(&v, *json),
+                        Err((_, e)) => {
+                            eprintln!("{e}");
+                            1
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to read {path}: {e}");
+                    1
+                }
+            },
+            _ => 0,
+        };
+        app_handle.exit(code);
+    });
+}
+
+pub fn is_headless() -> bool {
+    matches!(
+        LOCAL_COMMAND.get(),
+        Some(Command::Translate { .. }) | Some(Command::Ocr { .. })
+    )
+}
+
+// `pot show` without a running instance starts the app and opens the window
+pub fn run_startup() {
+    match LOCAL_COMMAND.get() {
+        Some(Command::Show(target)) => show(target),
+        _ => {
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            if !deep_link::handle_args(&args) {
+                handle_flags(&args);
+            }
+        }
+    }
+}
+
+// Arguments of a second launch, it quits right after sending them to us
+pub fn handle_second_instance(argv: &[String]) {
+    let args = argv.get(1..).unwrap_or_default();
+    if let Some(Ok(Command::Show(target))) = parse(args) {
+        return show(&target);
+    }
+    if deep_link::handle_args(args) {
+        return;
+    }
+    // Starting pot again without flags brings up the config window
+    if !handle_flags(args) {
+        config_window();
+    }
+}
+
+pub fn show(target: &str) {
+    match target {
+        "config" => config_window(),
+        "translate" => input_translate(),
+        _ => ocr_recognize(),
+    }
+}
+
+// `--translate [text]`, `--selection`, `--ocr`, `--ocr-translate` and `--config`
+// Returns false if none of them is given
+pub fn handle_flags(args: &[String]) -> bool {
+    let mut handled = false;
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--translate" => match iter.next_if(|v| !v.starts_with("--")) {
+                Some(text) => text_translate(text.clone()),
+                None => input_translate(),
+            },
+            "--selection" => selection_translate(),
+            "--ocr" => ocr_recognize(),
+            "--ocr-translate" => ocr_translate(),
+            "--config" => config_window(),
+            _ => continue,
+        }
+        handled = true;
+    }
+    handled
+}
+
+fn print_value(value: &Value) -> i32 {
+    match value {
+        Value::String(v) => println!("{v}"),
+        v => println!("{}", serde_json::to_string_pretty(v).unwrap()),
+    }
+    0
+}
+
+// Print the result of every service, exit code is 1 if all of them failed
+fn print_translate(value: &Value, json: bool) -> i32 {
+    if json {
+        return print_value(value);
+    }
+    let results = value["results"].as_array().cloned().unwrap_or_default();
+    let mut success = false;
+    for result in &results {
+        let service = result["service"].as_str().unwrap_or_default();
+        if let Some(e) = result["error"].as_str() {
+            eprintln!("[{service}] {e}");
+            continue;
+        }
+        success = true;
+        if results.len() > 1 {
+            println!("[{service}]");
+        }
+        print_value(&result["result"]);
+    }
+    if success {
+        0
+    } else {
+        1
+    }
+}
+
+fn print_ocr(value: &Value, json: bool) -> i32 {
+    if json {
+        return print_value(value);
+    }
+    print_value(&value["text"]);
+    match value.get("translation") {
+        Some(translation) => {
+            println!();
+            print_translate(translation, false)
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn identifier_matches_tauri_config() {
+        let config: Value = serde_json::from_str(include_str!("../tauri.conf.json")).unwrap();
+        assert_eq!(config["tauri"]["bundle"]["identifier"], IDENTIFIER);
+    }
+
+    #[test]
+    fn parse_options_splits_options_and_positional_arguments() {
+        let (positional, options) = parse_options(
+            &args(&["hello", "--to", "de", "world", "--json"]),
+            &["from", "to"],
+            &["json"],
+        )
+        .unwrap();
+        assert_eq!(positional, args(&["hello", "world"]));
+        assert_eq!(options.get("to").map(String::as_str), Some("de"));
+        assert_eq!(options.get("json").map(String::as_str), Some(""));
+        assert!(!options.contains_key("from"));
+    }
+
+    #[test]
+    fn parse_options_rejects_unknown_and_incomplete_options() {
+        assert_eq!(
+            parse_options(&args(&["--to"]), &["to"], &[]).unwrap_err(),
+            "Missing value for --to"
+        );
+        assert_eq!(
+            parse_options(&args(&["--verbose"]), &["to"], &["json"]).unwrap_err(),
+            "Unknown option: --verbose"
+        );
+        // A bare `--` is kept as a positional argument
+        let (positional, _) = parse_options(&args(&["--"]), &[], &[]).unwrap();
+        assert_eq!(positional, args(&["--"]));
+    }
+
+    #[test]
+    fn parse_ignores_plain_arguments() {
+        assert!(parse(&args(&["--translate"])).is_none());
+        assert!(parse(&args(&[])).is_none());
+        assert!(matches!(
+            parse(&args(&["show", "config"])),
+            Some(Ok(Command::Show(target))) if target == "config"
+        ));
+        assert!(matches!(parse(&args(&["show", "tray"])), Some(Err(_))));
+    }
+
+    #[test]
+    fn parse_config_set_takes_plain_words_as_strings() {
+        assert!(matches!(
+            parse(&args(&["config", "set", "app_language", "de"])),
+            Some(Ok(Command::ConfigSet(key, value))) if key == "app_language" && value == json!("de")
+        ));
+        assert!(matches!(
+            parse(&args(&["config", "set", "server_port", "60828"])),
+            Some(Ok(Command::ConfigSet(_, value))) if value == json!(60828)
+        ));
+    }
+}
@@ -1,4 +1,8 @@
-use crate::window::text_translate;
+use crate::app_rule::active_rule;
+use crate::server::broadcast;
+use crate::window::{set_window_options, text_translate, WindowOptions};
+use log::info;
+use serde_json::json;
 use std::sync::Mutex;
 use tauri::{ClipboardManager, Manager};
 
@@ -16,7 +20,22 @@ pub fn start_clipboard_monitor(app_handle: tauri::AppHandle) {
                         match result {
                             Some(v) => {
                                 if v != pre_text {
-                                    text_translate(v.clone());
+                                    // The clipboard has no owner to ask, this is the window focused
+                                    // when the change is seen, usually the one the text was copied in
+                                    match active_rule() {
+                                        Some(rule) if rule.ignore => {
+                                            info!("Clipboard text ignored by app rule");
+                                        }
+                                        rule => {
+                                            if let Some(rule) = rule {
+                                                let mut options = WindowOptions::default();
+                                                rule.apply(&mut options);
+                                                set_window_options("translate", options);
+                                            }
+                                            broadcast("clipboard", json!({ "text": v }));
+                                            text_translate(v.clone());
+                                        }
+                                    }
                                     pre_text = v;
                                 }
                             }
@@ -1,4 +1,4 @@
-use crate::config::get;
+use crate::config::AppConfig;
 use crate::config::StoreWrapper;
 use crate::error::Error;
 use crate::SelectionInfoWrapper;
@@ -111,24 +111,16 @@ pub fn copy_img(app_handle: tauri::AppHandle, width: usize, height: usize) -> Re
 
 #[tauri::command]
 pub fn set_proxy() -> Result<bool, ()> {
-    let host = match get("proxy_host") {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => return Err(()),
-    };
-    let port = match get("proxy_port") {
-        Some(v) => v.as_i64().unwrap(),
-        None => return Err(()),
-    };
-    let no_proxy = match get("no_proxy") {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => return Err(()),
-    };
-    let proxy = format!("http://{}:{}", host, port);
+    let config = AppConfig::load();
+    if config.proxy_host.is_empty() || config.proxy_port == 0 {
+        return Err(());
+    }
+    let proxy = format!("http://{}:{}", config.proxy_host, config.proxy_port);
 
     std::env::set_var("http_proxy", &proxy);
     std::env::set_var("https_proxy", &proxy);
     std::env::set_var("all_proxy", &proxy);
-    std::env::set_var("no_proxy", &no_proxy);
+    std::env::set_var("no_proxy", &config.no_proxy);
     Ok(true)
 }
 
@@ -143,6 +135,9 @@ pub fn unset_proxy() -> Result<bool, ()> {
 
 #[tauri::command]
 pub fn install_plugin(path_list: Vec<String>) -> Result<i32, Error> {
+    if crate::policy::POLICY.disable_plugins {
+        return Err(Error::Error("Plugins are disabled by policy".into()));
+    }
     let mut success_count = 0;
 
     for path in path_list {
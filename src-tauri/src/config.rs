@@ -1,9 +1,20 @@
-use crate::{error::Error, APP};
+use crate::app_rule::AppRule;
+use crate::clipboard::{start_clipboard_monitor, ClipboardMonitorEnableWrapper};
+use crate::cmd::{set_proxy, unset_proxy};
+use crate::hotkey::register_shortcut;
+use crate::policy::{is_locked, is_service_allowed, locked_value, POLICY};
+use crate::server::restart_server;
+use crate::tray::update_tray;
+use crate::{error::Error, secret, APP};
 use dirs::config_dir;
 use log::{info, warn};
-use serde_json::{json, Value};
+use notify::{RecursiveMode, Watcher};
+use once_cell::sync::{Lazy, OnceCell};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::{Manager, Wry};
+use tauri::{GlobalShortcutManager, Manager, Wry};
 use tauri_plugin_store::{Store, StoreBuilder};
 
 pub struct StoreWrapper(pub Mutex<Store<Wry>>);
@@ -13,7 +24,7 @@ pub fn init_config(app: &mut tauri::App) {
     let config_path = config_path.join(app.config().tauri.bundle.identifier.clone());
     let config_path = config_path.join("config.json");
     info!("Load config from: {:?}", config_path);
-    let mut store = StoreBuilder::new(app.handle(), config_path).build();
+    let mut store = StoreBuilder::new(app.handle(), config_path.clone()).build();
 
     match store.load() {
         Ok(_) => info!("Config loaded"),
@@ -22,11 +33,12 @@ pub fn init_config(app: &mut tauri::App) {
             info!("Config not found, creating new config");
         }
     }
+    migrate(&mut store, &config_path);
     app.manage(StoreWrapper(Mutex::new(store)));
     let _ = check_service_available();
 }
 
-fn check_available(list: Vec<String>, builtin: Vec<&str>, plugin: Vec<String>, key: &str) {
+fn check_available(list: Vec<String>, builtin: Vec<&str>, plugin: Vec<String>, kind: &str) {
     let origin_length = list.len();
     let mut new_list = list.clone();
     for service in list {
@@ -41,12 +53,18 @@ fn check_available(list: Vec<String>, builtin: Vec<&str>, plugin: Vec<String>, k
                 is_available = false;
             }
         }
+        if !is_service_allowed(kind, name) {
+            info!("Service {} is not allowed by policy", service);
+            is_available = false;
+        }
         if !is_available {
             new_list.retain(|x| x != &service);
         }
     }
     if new_list.len() != origin_length {
-        set(key, new_list);
+        let key = format!("{kind}_service_list");
+        set(&key, &new_list);
+        emit_changed(&key, &json!(new_list));
     }
 }
 
@@ -104,7 +122,7 @@ pub fn check_service_available() -> Result<(), Error> {
             recognize_service_list,
             builtin_recognize_list,
             plugin_recognize_list,
-            "recognize_service_list",
+            "recognize",
         );
     }
     if let Some(translate_service_list) = get("translate_service_list") {
@@ -113,17 +131,12 @@ pub fn check_service_available() -> Result<(), Error> {
             translate_service_list,
             builtin_translate_list,
             plugin_translate_list,
-            "translate_service_list",
+            "translate",
         );
     }
     if let Some(tts_service_list) = get("tts_service_list") {
         let tts_service_list: Vec<String> = serde_json::from_value(tts_service_list)?;
-        check_available(
-            tts_service_list,
-            builtin_tts_list,
-            plugin_tts_list,
-            "tts_service_list",
-        );
+        check_available(tts_service_list, builtin_tts_list, plugin_tts_list, "tts");
     }
     if let Some(collection_service_list) = get("collection_service_list") {
         let collection_service_list: Vec<String> = serde_json::from_value(collection_service_list)?;
@@ -131,7 +144,7 @@ pub fn check_service_available() -> Result<(), Error> {
             collection_service_list,
             builtin_collection_list,
             plugin_collection_list,
-            "collection_service_list",
+            "collection",
         );
     }
     Ok(())
@@ -155,9 +168,6 @@ pub fn get_plugin_list(plugin_type: &str) -> Option<Vec<String>> {
                 let name = entry.file_name().to_str()?.to_string();
                 if name.starts_with("plugin") {
                     plugin_list.push(name);
-                } else {
-                    // Remove old plugin
-                    let _ = std::fs::remove_dir_all(entry.path());
                 }
             }
         }
@@ -165,24 +175,1325 @@ pub fn get_plugin_list(plugin_type: &str) -> Option<Vec<String>> {
     Some(plugin_list)
 }
 
+// Upgrade steps for old configs, `MIGRATIONS[n]` upgrades version n to n + 1
+// Each step returns what it changed, for the log
+type Migration = fn(&mut Store<Wry>) -> Result<Vec<String>, Error>;
+const MIGRATIONS: [(&str, Migration); 3] = [
+    ("remove old plugins", remove_old_plugins),
+    ("move secrets", move_secrets),
+    ("name service instances", name_service_instances),
+];
+pub const CONFIG_VERSION: u64 = MIGRATIONS.len() as u64;
+
+fn migrate(store: &mut Store<Wry>, config_path: &std::path::Path) {
+    // New config, nothing to upgrade
+    if store.is_empty() {
+        let _ = store.insert("config_version".to_string(), json!(CONFIG_VERSION));
+        return;
+    }
+    let version = match store.get("config_version") {
+        Some(v) => v.as_u64().unwrap_or(0),
+        None => 0,
+    };
+    if version >= CONFIG_VERSION {
+        return;
+    }
+    info!(
+        "Migrate config from version {} to {}",
+        version, CONFIG_VERSION
+    );
+    let backup_path = config_path.with_file_name(format!("config.v{version}.json.bak"));
+    if let Err(e) = std::fs::copy(config_path, &backup_path) {
+        warn!("Failed to back up config, skip migration: {}", e);
+        return;
+    }
+    info!("Config backed up to: {:?}", backup_path);
+    for (index, (name, step)) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        match step(store) {
+            Ok(changes) => {
+                for change in changes {
+                    info!("Migration {}: {}", name, change);
+                }
+            }
+            Err(e) => {
+                warn!("Migration {} failed: {}", name, e);
+                break;
+            }
+        }
+        let _ = store.insert("config_version".to_string(), json!(index + 1));
+    }
+    if let Err(e) = store.save() {
+        warn!("Failed to save migrated config: {:?}", e);
+    }
+}
+
+// Plugins of the old format are not supported anymore
+fn remove_old_plugins(_: &mut Store<Wry>) -> Result<Vec<String>, Error> {
+    let app_handle = APP.get().unwrap();
+    let plugin_dir = config_dir()
+        .ok_or(Error::Error("Get Config Dir Failed".into()))?
+        .join(app_handle.config().tauri.bundle.identifier.clone())
+        .join("plugins");
+    let mut changes = vec![];
+    for plugin_type in ["translate", "recognize", "tts", "collection"] {
+        let dir = plugin_dir.join(plugin_type);
+        if !dir.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() && !name.starts_with("plugin") {
+                std::fs::remove_dir_all(entry.path())?;
+                changes.push(format!("removed {plugin_type} plugin {name}"));
+            }
+        }
+    }
+    Ok(changes)
+}
+
+// Api keys and passwords used to be plain text in config.json
+fn move_secrets(store: &mut Store<Wry>) -> Result<Vec<String>, Error> {
+    let entries: Vec<(String, Value)> = store
+        .entries()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let mut changes = vec![];
+    for (key, value) in entries {
+        let sealed = secret::seal(&key, value.clone())?;
+        if sealed != value {
+            store
+                .insert(key.clone(), sealed)
+                .map_err(|e| Error::Error(e.to_string().into()))?;
+            changes.push(format!("moved secrets of {key} to secret storage"));
+        }
+    }
+    Ok(changes)
+}
+
+// Service lists used to have plain names like `deepl`, now each entry is an instance `deepl@<id>`
+// with its config under that key. A name in several lists gets an instance in each
+fn name_service_instances(store: &mut Store<Wry>) -> Result<Vec<String>, Error> {
+    use rand::distributions::Alphanumeric;
+    use rand::Rng;
+
+    let store_error = |e: tauri_plugin_store::Error| Error::Error(e.to_string().into());
+    let mut renamed = HashSet::new();
+    let mut changes = vec![];
+    for list in SERVICE_LISTS {
+        let Some(Value::Array(services)) = store.get(list).cloned() else {
+            continue;
+        };
+        let mut instances = vec![];
+        for service in services {
+            let name = match service.as_str() {
+                Some(name) if !name.is_empty() && !name.contains('@') => name.to_string(),
+                _ => {
+                    instances.push(service);
+                    continue;
+                }
+            };
+            let id: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect();
+            let instance = format!("{name}@{id}");
+            if let Some(config) = store.get(&name).cloned() {
+                store
+                    .insert(instance.clone(), config)
+                    .map_err(store_error)?;
+            }
+            changes.push(format!("{name} in {list} is now {instance}"));
+            renamed.insert(name);
+            instances.push(json!(instance));
+        }
+        store
+            .insert(list.to_string(), json!(instances))
+            .map_err(store_error)?;
+    }
+    // The old keys only go once every list has its copy
+    for name in renamed {
+        if store.has(&name) {
+            store.delete(&name).map_err(store_error)?;
+        }
+    }
+    Ok(changes)
+}
+
+// Typed view of the config used by the backend
+// Missing or invalid values fall back to the default, a hand-edited config.json can't crash the app
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AppConfig {
+    pub app_language: String,
+    pub check_update: bool,
+    pub tray_click_event: String,
+    pub clipboard_monitor: bool,
+    pub translate_source_language: String,
+    pub translate_target_language: String,
+    pub translate_auto_copy: String,
+    pub translate_detect_engine: String,
+    pub translate_service_list: Vec<String>,
+    pub translate_window_position: String,
+    pub translate_window_position_x: i32,
+    pub translate_window_position_y: i32,
+    pub translate_window_width: u32,
+    pub translate_window_height: u32,
+    pub recognize_language: String,
+    pub recognize_service_list: Vec<String>,
+    pub recognize_window_width: u32,
+    pub recognize_window_height: u32,
+    pub tts_service_list: Vec<String>,
+    pub hotkey_selection_translate: String,
+    pub hotkey_input_translate: String,
+    pub hotkey_ocr_recognize: String,
+    pub hotkey_ocr_translate: String,
+    pub server_enable: bool,
+    pub server_port: u16,
+    pub server_address: String,
+    pub server_auth: bool,
+    // Still the `secret://` reference, `secret::reveal` reads it when it is needed
+    pub server_token: String,
+    pub server_api_timeout: u64,
+    pub server_unix_socket: String,
+    pub server_unix_socket_mode: u32,
+    pub proxy_enable: bool,
+    pub proxy_host: String,
+    pub proxy_port: u16,
+    pub no_proxy: String,
+    pub app_rules: Vec<AppRule>,
+    pub backup_type: String,
+    pub backup_schedule: String,
+    pub backup_keep_last: u32,
+    pub backup_keep_daily: u32,
+    pub backup_keep_weekly: u32,
+    pub backup_local_dir: String,
+    pub backup_last_run: i64,
+    pub backup_merge_history: bool,
+}
+
+// Same defaults as the frontend
+pub const TRANSLATE_SERVICES: [&str; 6] = ["deepl", "bing", "lingva", "yandex", "google", "ecdict"];
+pub const RECOGNIZE_SERVICES: [&str; 2] = ["system", "tesseract"];
+pub const TTS_SERVICES: [&str; 1] = ["lingva_tts"];
+
+impl AppConfig {
+    pub fn load() -> Self {
+        let state = APP.get().unwrap().state::<StoreWrapper>();
+        let store = state.0.lock().unwrap();
+        Self::read(&Reader(Some(&store)))
+    }
+
+    // What is used when nothing is set
+    pub fn defaults() -> Self {
+        Self::read(&Reader(None))
+    }
+
+    fn read(r: &Reader) -> Self {
+        Self {
+            app_language: r.string("app_language", "en"),
+            check_update: r.bool("check_update", true),
+            tray_click_event: r.one_of(
+                "tray_click_event",
+                &[
+                    "config",
+                    "translate",
+                    "ocr_recognize",
+                    "ocr_translate",
+                    "disable",
+                ],
+                "config",
+            ),
+            clipboard_monitor: r.bool("clipboard_monitor", false),
+            translate_source_language: r.language("translate_source_language", "auto"),
+            translate_target_language: r.language("translate_target_language", "zh_cn"),
+            translate_auto_copy: r.one_of(
+                "translate_auto_copy",
+                &["source", "target", "source_target", "disable"],
+                "disable",
+            ),
+            translate_detect_engine: r.string("translate_detect_engine", "baidu"),
+            translate_service_list: r.list("translate_service_list", &TRANSLATE_SERVICES),
+            translate_window_position: r.one_of(
+                "translate_window_position",
+                &["mouse", "pre_state"],
+                "mouse",
+            ),
+            translate_window_position_x: r.int(
+                "translate_window_position_x",
+                0,
+                i32::MIN as i64..=i32::MAX as i64,
+            ),
+            translate_window_position_y: r.int(
+                "translate_window_position_y",
+                0,
+                i32::MIN as i64..=i32::MAX as i64,
+            ),
+            translate_window_width: r.int("translate_window_width", 350, 100..=10000),
+            translate_window_height: r.int("translate_window_height", 420, 100..=10000),
+            recognize_language: r.language("recognize_language", "auto"),
+            recognize_service_list: r.list("recognize_service_list", &RECOGNIZE_SERVICES),
+            recognize_window_width: r.int("recognize_window_width", 800, 100..=10000),
+            recognize_window_height: r.int("recognize_window_height", 400, 100..=10000),
+            tts_service_list: r.list("tts_service_list", &TTS_SERVICES),
+            hotkey_selection_translate: r.string("hotkey_selection_translate", ""),
+            hotkey_input_translate: r.string("hotkey_input_translate", ""),
+            hotkey_ocr_recognize: r.string("hotkey_ocr_recognize", ""),
+            hotkey_ocr_translate: r.string("hotkey_ocr_translate", ""),
+            server_enable: r.bool("server_enable", true),
+            server_port: r.int("server_port", 60828, 0..=65535),
+            server_address: r.string("server_address", "127.0.0.1"),
+            server_auth: r.bool("server_auth", false),
+            server_token: r.string("server_token", ""),
+            server_api_timeout: r.int("server_api_timeout", 30, 1..=3600),
+            server_unix_socket: r.string("server_unix_socket", ""),
+            server_unix_socket_mode: r.octal("server_unix_socket_mode", 0o600),
+            proxy_enable: r.bool("proxy_enable", false),
+            proxy_host: r.string("proxy_host", ""),
+            proxy_port: r.int("proxy_port", 0, 0..=65535),
+            no_proxy: r.string("no_proxy", "localhost,127.0.0.1"),
+            app_rules: r.rules("app_rules"),
+            backup_type: r.one_of(
+                "backup_type",
+                &["webdav", "aliyun", "s3", "local"],
+                "webdav",
+            ),
+            backup_schedule: r.one_of(
+                "backup_schedule",
+                &["disable", "daily", "weekly"],
+                "disable",
+            ),
+            backup_keep_last: r.int("backup_keep_last", 7, 0..=1000),
+            backup_keep_daily: r.int("backup_keep_daily", 7, 0..=1000),
+            backup_keep_weekly: r.int("backup_keep_weekly", 4, 0..=1000),
+            backup_local_dir: r.string("backup_local_dir", ""),
+            // Unix time of the last automatic backup
+            backup_last_run: r.int("backup_last_run", 0, 0..=i64::MAX),
+            backup_merge_history: r.bool("backup_merge_history", false),
+        }
+    }
+
+    pub fn hotkey(&self, name: &str) -> &str {
+        match name {
+            "hotkey_selection_translate" => &self.hotkey_selection_translate,
+            "hotkey_input_translate" => &self.hotkey_input_translate,
+            "hotkey_ocr_recognize" => &self.hotkey_ocr_recognize,
+            "hotkey_ocr_translate" => &self.hotkey_ocr_translate,
+            _ => "",
+        }
+    }
+}
+
+// Invalid values already warned about, don't fill the log on every load
+static WARNED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Reads the overrides and the store, or only the defaults without a store
+struct Reader<'a>(Option<&'a Store<Wry>>);
+
+impl Reader<'_> {
+    fn get(&self, key: &str) -> Option<&Value> {
+        let store = self.0?;
+        match get_override(key) {
+            Some((value, _)) => Some(value),
+            None => store.get(key),
+        }
+        .filter(|v| !v.is_null())
+    }
+
+    fn invalid<T: std::fmt::Debug>(&self, key: &str, value: &Value, default: T) -> T {
+        if WARNED.lock().unwrap().insert(format!("{key}={value}")) {
+            warn!("Invalid config {}: {}, using {:?}", key, value, default);
+        }
+        default
+    }
+
+    // Secret references are left as they are, the store is locked while reading
+    fn string(&self, key: &str, default: &str) -> String {
+        match self.get(key) {
+            Some(Value::String(v)) => v.clone(),
+            Some(v) => self.invalid(key, v, default.to_string()),
+            None => default.to_string(),
+        }
+    }
+
+    fn one_of(&self, key: &str, allowed: &[&str], default: &str) -> String {
+        match self.get(key) {
+            Some(Value::String(v)) if allowed.contains(&v.as_str()) => v.clone(),
+            Some(v) => self.invalid(key, v, default.to_string()),
+            None => default.to_string(),
+        }
+    }
+
+    fn language(&self, key: &str, default: &str) -> String {
+        match self.get(key) {
+            Some(Value::String(v))
+                if !v.is_empty() && v.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                v.clone()
+            }
+            Some(v) => self.invalid(key, v, default.to_string()),
+            None => default.to_string(),
+        }
+    }
+
+    fn bool(&self, key: &str, default: bool) -> bool {
+        match self.get(key) {
+            Some(Value::Bool(v)) => *v,
+            Some(Value::String(v)) if v == "true" || v == "false" => v == "true",
+            Some(v) => self.invalid(key, v, default),
+            None => default,
+        }
+    }
+
+    // Numbers are sometimes saved as floats or strings by the frontend
+    fn int<T>(&self, key: &str, default: T, range: std::ops::RangeInclusive<i64>) -> T
+    where
+        T: TryFrom<i64> + std::fmt::Debug,
+    {
+        let Some(value) = self.get(key) else {
+            return default;
+        };
+        let number = match value {
+            Value::Number(v) => v.as_i64().or(v.as_f64().map(|v| v.round() as i64)),
+            Value::String(v) => v.trim().parse().ok(),
+            _ => None,
+        };
+        match number
+            .filter(|v| range.contains(v))
+            .and_then(|v| T::try_from(v).ok())
+        {
+            Some(v) => v,
+            None => self.invalid(key, value, default),
+        }
+    }
+
+    fn octal(&self, key: &str, default: u32) -> u32 {
+        match self.get(key) {
+            Some(Value::String(v)) => match u32::from_str_radix(v, 8) {
+                Ok(mode) if mode <= 0o777 => mode,
+                _ => self.invalid(key, &json!(v), default),
+            },
+            Some(v) => self.invalid(key, v, default),
+            None => default,
+        }
+    }
+
+    fn list(&self, key: &str, default: &[&str]) -> Vec<String> {
+        match self.get(key) {
+            Some(v) => match serde_json::from_value(v.clone()) {
+                Ok(list) => list,
+                Err(_) => self.invalid(key, v, default.iter().map(|v| v.to_string()).collect()),
+            },
+            None => default.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    // Broken rules are skipped, the others still apply
+    fn rules(&self, key: &str) -> Vec<AppRule> {
+        match self.get(key) {
+            Some(Value::Array(list)) => list
+                .iter()
+                .filter_map(|v| match serde_json::from_value::<AppRule>(v.clone()) {
+                    Ok(rule) if rule.is_valid() => Some(rule),
+                    _ => self.invalid(key, v, None),
+                })
+                .collect(),
+            Some(v) => self.invalid(key, v, vec![]),
+            None => vec![],
+        }
+    }
+}
+
+// Where a config value comes from, the first layer that has the key wins
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigSource {
+    // The system policy file
+    Policy,
+    // `pot --set key=value`
+    Cli,
+    // `POT_KEY=value`
+    Env,
+    // config.json
+    Stored,
+    Default,
+}
+
+// Overrides are only for this run and never saved, e.g. for kiosk and CI setups
+static CLI_OVERRIDES: OnceCell<Map<String, Value>> = OnceCell::new();
+// Only `POT_<KEY>` for a key pot knows, other `POT_` variables may belong to something else
+pub static ENV_OVERRIDES: Lazy<Map<String, Value>> = Lazy::new(|| {
+    let known = env_keys();
+    let mut overrides = Map::new();
+    for (name, value) in std::env::vars() {
+        let Some(key) = name.strip_prefix("POT_").map(str::to_lowercase) else {
+            continue;
+        };
+        if known.contains(&key) {
+            overrides.insert(key, parse_override(&value));
+        } else {
+            warn!(
+                "Ignore environment variable {}, {} is not a config key",
+                name, key
+            );
+        }
+    }
+    overrides
+});
+
+// The backend settings and the ones only the windows read, service instances can't be env names
+fn env_keys() -> HashSet<String> {
+    let mut keys: HashSet<String> = match serde_json::to_value(AppConfig::defaults()) {
+        Ok(Value::Object(defaults)) => defaults.into_iter().map(|(key, _)| key).collect(),
+        _ => HashSet::new(),
+    };
+    keys.extend(
+        APPEARANCE_KEYS
+            .iter()
+            .chain(&SERVICE_LISTS)
+            .map(|key| key.to_string()),
+    );
+    keys
+}
+
+// Plain words don't need to be quoted as json strings
+fn parse_override(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or(json!(value))
+}
+
+// Collect `--set key=value` from the launch arguments
+pub fn init_overrides(args: &[String]) {
+    let mut overrides = Map::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let item = match arg.strip_prefix("--set=") {
+            Some(item) => item,
+            None if arg == "--set" => match iter.next() {
+                Some(item) => item.as_str(),
+                None => break,
+            },
+            None => continue,
+        };
+        match item.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                overrides.insert(key.to_string(), parse_override(value));
+            }
+            _ => warn!("Invalid override, expected key=value: {}", item),
+        }
+    }
+    let _ = CLI_OVERRIDES.set(overrides);
+}
+
+fn get_override(key: &str) -> Option<(&'static Value, ConfigSource)> {
+    if let Some(value) = locked_value(key) {
+        return Some((value, ConfigSource::Policy));
+    }
+    if let Some(value) = CLI_OVERRIDES.get().and_then(|v| v.get(key)) {
+        return Some((value, ConfigSource::Cli));
+    }
+    ENV_OVERRIDES
+        .get(key)
+        .map(|value| (value, ConfigSource::Env))
+}
+
+// All overrides, with the policy over the cli ones over the environment
+pub fn overrides() -> Map<String, Value> {
+    let mut overrides = ENV_OVERRIDES.clone();
+    if let Some(cli) = CLI_OVERRIDES.get() {
+        overrides.extend(cli.clone());
+    }
+    overrides.extend(POLICY.locked.clone());
+    overrides
+}
+
 pub fn get(key: &str) -> Option<Value> {
+    if let Some((value, _)) = get_override(key) {
+        return Some(value.clone());
+    }
     let state = APP.get().unwrap().state::<StoreWrapper>();
-    let store = state.0.lock().unwrap();
-    match store.get(key) {
-        Some(value) => Some(value.clone()),
-        None => None,
+    let value = state.0.lock().unwrap().get(key).cloned();
+    value.map(secret::open)
+}
+
+// The value with the layer it comes from, defaults are only known for the keys the backend uses
+pub fn get_with_source(key: &str) -> Option<(Value, ConfigSource)> {
+    if let Some((value, source)) = get_override(key) {
+        return Some((value.clone(), source));
+    }
+    if let Some(value) = get_stored(key) {
+        return Some((value, ConfigSource::Stored));
     }
+    let defaults = serde_json::to_value(AppConfig::defaults()).ok()?;
+    defaults
+        .get(key)
+        .map(|value| (value.clone(), ConfigSource::Default))
+}
+
+// The value in config.json, without the overrides
+fn get_stored(key: &str) -> Option<Value> {
+    let state = APP.get().unwrap().state::<StoreWrapper>();
+    let value = state.0.lock().unwrap().get(key).cloned();
+    value.map(secret::open)
 }
 
 pub fn set<T: serde::ser::Serialize>(key: &str, value: T) {
+    if is_locked(key) {
+        warn!("Config {} is locked by policy", key);
+        return;
+    }
     let state = APP.get().unwrap().state::<StoreWrapper>();
+    let value = secret::seal(key, json!(value)).unwrap_or_else(|e| {
+        warn!("Failed to store secrets of {}: {}", key, e);
+        json!(value)
+    });
     let mut store = state.0.lock().unwrap();
-    store.insert(key.to_string(), json!(value)).unwrap();
+    store.insert(key.to_string(), value).unwrap();
     store.save().unwrap();
 }
 
+// Tell the windows about the new value, the same event `useConfig` sends
+pub fn emit_changed(key: &str, value: &Value) {
+    let event = format!("{}_changed", key.replace('.', "_").replace('@', ":"));
+    let _ = APP.get().unwrap().emit_all(&event, value);
+}
+
+pub const SECRET_MASK: &str = "******";
+
+// Passwords, tokens and api keys, in the config or in a service instance config
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase().replace(['_', '-'], "");
+    if key.starts_with("hotkey") {
+        return false;
+    }
+    key.contains("password")
+        || key.contains("secret")
+        || key.contains("token")
+        || key.ends_with("key")
+}
+
+pub fn mask_secrets(key: &str, value: &Value) -> Value {
+    match value {
+        Value::String(v) if is_secret_key(key) && !v.is_empty() => json!(SECRET_MASK),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), mask_secrets(k, v)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+// Keep the stored secret where a masked value is written back
+pub fn unmask_secrets(value: Value, old: Option<&Value>) -> Value {
+    match value {
+        Value::String(v) if v == SECRET_MASK => match old {
+            Some(old) => old.clone(),
+            None => json!(v),
+        },
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let old = old.and_then(|old| old.get(&k));
+                    (k, unmask_secrets(v, old))
+                })
+                .collect(),
+        ),
+        _ => value,
+    }
+}
+
 pub fn is_first_run() -> bool {
     let state = APP.get().unwrap().state::<StoreWrapper>();
     let store = state.0.lock().unwrap();
-    store.is_empty()
+    // `config_version` is written on the first run
+    store.is_empty() || (store.len() == 1 && store.has("config_version"))
+}
+
+// Named profiles, each a full copy of the config in `profiles/<name>.json`
+// config.json is always the active profile, `profile` is its name
+pub const DEFAULT_PROFILE: &str = "default";
+
+fn profile_dir() -> Result<PathBuf, Error> {
+    let app_handle = APP.get().unwrap();
+    let dir = config_dir()
+        .ok_or(Error::Error("Get Config Dir Failed".into()))?
+        .join(app_handle.config().tauri.bundle.identifier.clone())
+        .join("profiles");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn check_profile_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(Error::Error(format!("Invalid Profile Name: {name}").into()));
+    }
+    Ok(())
+}
+
+pub fn active_profile() -> String {
+    match get("profile") {
+        Some(Value::String(v)) if !v.is_empty() => v,
+        _ => DEFAULT_PROFILE.to_string(),
+    }
+}
+
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles = vec![active_profile()];
+    let entries = profile_dir()
+        .ok()
+        .and_then(|dir| std::fs::read_dir(dir).ok());
+    if let Some(entries) = entries {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|v| v.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|v| v.to_str()) {
+                profiles.push(name.to_string());
+            }
+        }
+    }
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+// Save the active profile and load another one, a new profile starts as a copy of the active one
+pub fn switch_profile(name: &str) -> Result<(), Error> {
+    check_profile_name(name)?;
+    let active = active_profile();
+    if name == active {
+        return Ok(());
+    }
+    let dir = profile_dir()?;
+    // The keyring is only used with the store unlocked
+    let current = stored_entries();
+
+    // Each profile has its own secrets, the live config has the plain ids
+    let mut saved = Map::new();
+    for (key, value) in &current {
+        let value = secret::seal_in(
+            &format!("profile/{active}"),
+            key,
+            secret::open(value.clone()),
+        )?;
+        saved.insert(key.clone(), value);
+    }
+    std::fs::write(
+        dir.join(format!("{active}.json")),
+        serde_json::to_string_pretty(&saved)?,
+    )?;
+
+    let next = match std::fs::read_to_string(dir.join(format!("{name}.json"))) {
+        Ok(data) => {
+            let mut next = Map::new();
+            for (key, value) in serde_json::from_str::<Map<String, Value>>(&data)? {
+                let value = secret::seal(&key, secret::open(value))?;
+                next.insert(key, value);
+            }
+            next
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => current.clone(),
+        Err(e) => return Err(e.into()),
+    };
+    let store_error = |e: tauri_plugin_store::Error| Error::Error(e.to_string().into());
+    let state = APP.get().unwrap().state::<StoreWrapper>();
+    let mut store = state.0.lock().unwrap();
+    store.clear().map_err(store_error)?;
+    for (key, value) in &next {
+        store
+            .insert(key.clone(), value.clone())
+            .map_err(store_error)?;
+    }
+    store
+        .insert("profile".to_string(), json!(name))
+        .map_err(store_error)?;
+    store.save().map_err(store_error)?;
+    drop(store);
+    info!("Switched profile from {} to {}", active, name);
+
+    apply_changes(&current, &next, true);
+    let _ = APP.get().unwrap().emit_all("profile_changed", name);
+    Ok(())
+}
+
+pub fn delete_profile(name: &str) -> Result<(), Error> {
+    check_profile_name(name)?;
+    if name == active_profile() {
+        return Err(Error::Error("Can't Delete The Active Profile".into()));
+    }
+    let path = profile_dir()?.join(format!("{name}.json"));
+    let data = std::fs::read_to_string(&path)?;
+    for (key, value) in serde_json::from_str::<Map<String, Value>>(&data)? {
+        delete_secrets(&format!("profile/{name}/{key}"), &value);
+    }
+    std::fs::remove_file(path)?;
+    info!("Deleted profile {}", name);
+    Ok(())
+}
+
+fn delete_secrets(id: &str, value: &Value) {
+    match value {
+        Value::String(v) if v.starts_with(secret::REFERENCE_PREFIX) => {
+            if let Err(e) = secret::delete_secret(v) {
+                warn!("Failed to delete secret {}: {}", id, e);
+            }
+        }
+        Value::Object(map) => {
+            for (k, v) in map {
+                delete_secrets(&format!("{id}.{k}"), v);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Parts of the config that can be shared on their own, e.g. a team-standard setup
+pub const CONFIG_SECTIONS: [&str; 4] = ["hotkeys", "services", "appearance", "plugins"];
+const APPEARANCE_KEYS: [&str; 9] = [
+    "app_language",
+    "app_theme",
+    "app_font",
+    "app_fallback_font",
+    "app_font_size",
+    "transparent",
+    "translate_font_size",
+    "hide_source",
+    "hide_language",
+];
+const SERVICE_LISTS: [&str; 4] = [
+    "translate_service_list",
+    "recognize_service_list",
+    "tts_service_list",
+    "collection_service_list",
+];
+// Values are grouped by section, an instance can't be told apart from other keys without its list
+const EXPORT_FORMAT: u64 = 2;
+
+// Service instances are stored under their name in the service lists, e.g. `deepl@x1y2`
+fn config_section(key: &str, config: &Map<String, Value>) -> Option<&'static str> {
+    if key.starts_with("hotkey_") {
+        return Some("hotkeys");
+    }
+    if APPEARANCE_KEYS.contains(&key) {
+        return Some("appearance");
+    }
+    if SERVICE_LISTS.contains(&key) {
+        return Some("services");
+    }
+    let listed = SERVICE_LISTS
+        .iter()
+        .filter_map(|list| config.get(*list)?.as_array())
+        .flatten()
+        .any(|v| v.as_str() == Some(key));
+    match listed {
+        true if key.starts_with("plugin") => Some("plugins"),
+        true => Some("services"),
+        false => None,
+    }
+}
+
+pub fn stored_entries() -> Map<String, Value> {
+    let state = APP.get().unwrap().state::<StoreWrapper>();
+    let store = state.0.lock().unwrap();
+    store
+        .entries()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+// Secrets are redacted, the file is meant to be passed around
+pub fn export_sections(path: &str, sections: &[String]) -> Result<usize, Error> {
+    if let Some(section) = sections
+        .iter()
+        .find(|v| !CONFIG_SECTIONS.contains(&v.as_str()))
+    {
+        return Err(Error::Error(
+            format!("Unknown Config Section: {section}").into(),
+        ));
+    }
+    let config = stored_entries();
+    let mut exported: Map<String, Value> = sections
+        .iter()
+        .map(|section| (section.clone(), json!({})))
+        .collect();
+    let mut insert = |section: &str, key: &String, value: Value| {
+        if let Some(Value::Object(entries)) = exported.get_mut(section) {
+            entries.insert(key.clone(), value);
+        }
+    };
+    for (key, value) in &config {
+        let Some(section) = config_section(key, &config) else {
+            continue;
+        };
+        if !SERVICE_LISTS.contains(&key.as_str()) {
+            insert(section, key, mask_secrets(key, value));
+            continue;
+        }
+        // Plugin entries go with the plugins, so a list never names an instance the file lacks
+        let (plugins, services): (Vec<Value>, Vec<Value>) = value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .cloned()
+            .partition(|v| v.as_str().is_some_and(|v| v.starts_with("plugin")));
+        for (section, list) in [("services", services), ("plugins", plugins)] {
+            if !list.is_empty() {
+                insert(section, key, Value::Array(list));
+            }
+        }
+    }
+    let count = exported
+        .values()
+        .filter_map(Value::as_object)
+        .map(Map::len)
+        .sum();
+    let data = json!({
+        "format": EXPORT_FORMAT,
+        "config_version": CONFIG_VERSION,
+        "config": exported,
+    });
+    std::fs::write(path, serde_json::to_string_pretty(&data)?)?;
+    info!("Exported {} config values to {}", count, path);
+    Ok(count)
+}
+
+// The sections in the file with their values
+type ExportSections = Vec<(&'static str, Map<String, Value>)>;
+
+fn read_export(path: &str) -> Result<ExportSections, Error> {
+    let data: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    if data.get("format").and_then(Value::as_u64) != Some(EXPORT_FORMAT) {
+        return Err(Error::Error("Invalid Config Export".into()));
+    }
+    if data["config_version"].as_u64().unwrap_or(0) > CONFIG_VERSION {
+        return Err(Error::Error("Config Export Is From A Newer Version".into()));
+    }
+    let Some(Value::Object(config)) = data.get("config") else {
+        return Err(Error::Error("Invalid Config Export".into()));
+    };
+    let sections = CONFIG_SECTIONS
+        .into_iter()
+        .filter_map(|section| match config.get(section) {
+            Some(Value::Object(entries)) => Some((section, entries.clone())),
+            _ => None,
+        })
+        .collect();
+    Ok(sections)
+}
+
+// A redacted secret without a local one is left empty to be filled in
+fn drop_masks(value: Value) -> Value {
+    match value {
+        Value::String(v) if v == SECRET_MASK => Value::String(String::new()),
+        Value::Object(map) => {
+            Value::Object(map.into_iter().map(|(k, v)| (k, drop_masks(v))).collect())
+        }
+        _ => value,
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ImportChange {
+    pub key: String,
+    pub section: &'static str,
+    // Both masked, for the preview
+    pub local: Option<Value>,
+    pub imported: Value,
+    // The key has another value here, service lists are merged instead
+    pub conflict: bool,
+}
+
+// The changes an import would make, with the values to save
+fn plan_import(path: &str, sections: &[String]) -> Result<Vec<(ImportChange, Value)>, Error> {
+    let file = read_export(path)?;
+    let local = stored_entries();
+    // Instances are the entries of the local lists and of the lists in the file, whatever section
+    let mut lists = Map::new();
+    for list in SERVICE_LISTS {
+        let entries: Vec<&Value> = file
+            .iter()
+            .filter_map(|(_, entries)| entries.get(list)?.as_array())
+            .chain(local.get(list).and_then(Value::as_array))
+            .flatten()
+            .collect();
+        lists.insert(list.to_string(), json!(entries));
+    }
+    // Service lists can be in both sections, the plugins merge onto the services
+    let mut planned = local.clone();
+    let mut changes = vec![];
+    for (section, entries) in file {
+        if !sections.iter().any(|v| v == section) {
+            continue;
+        }
+        for (key, value) in entries {
+            let is_list = SERVICE_LISTS.contains(&key.as_str());
+            // A key filed under another section is not what the user picked
+            let belongs = match is_list {
+                true => section == "services" || section == "plugins",
+                false => config_section(&key, &lists) == Some(section),
+            };
+            if !belongs || is_locked(&key) {
+                continue;
+            }
+            let old = planned.get(&key);
+            let value = drop_masks(unmask_secrets(value, old));
+            // Local services are kept, the imported ones are added after them
+            let value = match (old, value) {
+                (Some(Value::Array(old)), Value::Array(new)) if is_list => {
+                    let mut merged = old.clone();
+                    merged.extend(new.into_iter().filter(|v| !old.contains(v)));
+                    Value::Array(merged)
+                }
+                (_, value) => value,
+            };
+            if old == Some(&value) {
+                continue;
+            }
+            let change = ImportChange {
+                key: key.clone(),
+                section,
+                local: old.map(|v| mask_secrets(&key, v)),
+                imported: mask_secrets(&key, &value),
+                conflict: old.is_some() && !is_list,
+            };
+            planned.insert(key, value.clone());
+            changes.push((change, value));
+        }
+    }
+    Ok(changes)
+}
+
+// `keep` are the conflicting keys to leave as they are
+pub fn import_sections(path: &str, sections: &[String], keep: &[String]) -> Result<usize, Error> {
+    let mut sealed = vec![];
+    for (change, value) in plan_import(path, sections)? {
+        if change.conflict && keep.contains(&change.key) {
+            continue;
+        }
+        let value = secret::seal(&change.key, value)?;
+        sealed.push((change, value));
+    }
+    let state = APP.get().unwrap().state::<StoreWrapper>();
+    let mut store = state.0.lock().unwrap();
+    let current: Map<String, Value> = store
+        .entries()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let mut next = current.clone();
+    let store_error = |e: tauri_plugin_store::Error| Error::Error(e.to_string().into());
+    for (change, value) in sealed {
+        store
+            .insert(change.key.clone(), value.clone())
+            .map_err(store_error)?;
+        next.insert(change.key, value);
+    }
+    store.save().map_err(store_error)?;
+    drop(store);
+    let count = next
+        .iter()
+        .filter(|(k, v)| current.get(*k) != Some(v))
+        .count();
+    info!("Imported {} config values from {}", count, path);
+    apply_changes(&current, &next, false);
+    Ok(count)
+}
+
+// Bring the running app in line with the changed config
+// `reapply` registers the hotkeys and sets the proxy even if they didn't change, as for a new profile
+pub fn apply_changes(old: &Map<String, Value>, new: &Map<String, Value>, reapply: bool) {
+    let mut changed: Vec<&String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .collect();
+    changed.sort();
+    changed.dedup();
+    let touched = |prefixes: &[&str]| {
+        changed
+            .iter()
+            .any(|key| prefixes.iter().any(|prefix| key.starts_with(prefix)))
+    };
+    let app_handle = APP.get().unwrap();
+    let config = AppConfig::load();
+    if reapply || touched(&["hotkey_"]) {
+        let _ = app_handle.global_shortcut_manager().unregister_all();
+        if let Err(e) = register_shortcut("all") {
+            warn!("Failed to register shortcuts: {}", e);
+        }
+    }
+    if reapply || touched(&["proxy_", "no_proxy"]) {
+        if config.proxy_enable {
+            let _ = set_proxy();
+        } else {
+            let _ = unset_proxy();
+        }
+    }
+    if touched(&["clipboard_monitor"]) {
+        let state = app_handle.state::<ClipboardMonitorEnableWrapper>();
+        let mut enable = state.0.lock().unwrap();
+        let running = enable.as_str() == "true";
+        enable.replace_range(.., &config.clipboard_monitor.to_string());
+        drop(enable);
+        if config.clipboard_monitor && !running {
+            start_clipboard_monitor(app_handle.clone());
+        }
+    }
+    if touched(&SERVICE_LISTS) {
+        let _ = check_service_available();
+    }
+    if touched(&[
+        "server_enable",
+        "server_port",
+        "server_address",
+        "server_unix_socket",
+    ]) {
+        restart_server();
+    }
+    update_tray(app_handle.clone(), "".to_string(), "".to_string());
+    for key in changed {
+        // Windows keep showing the override
+        if get_override(key).is_some() {
+            continue;
+        }
+        // `useConfig` falls back to the default for null
+        let value = new
+            .get(key)
+            .cloned()
+            .map(secret::open)
+            .unwrap_or(Value::Null);
+        emit_changed(key, &value);
+    }
+}
+
+// Apply edits of config.json made outside of pot, e.g. by a dotfiles manager
+pub fn watch_config() {
+    let Some(dir) = config_dir() else {
+        return;
+    };
+    let app_handle = APP.get().unwrap();
+    let config_path = dir
+        .join(app_handle.config().tauri.bundle.identifier.clone())
+        .join("config.json");
+    std::thread::spawn(move || {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to watch config: {}", e);
+                return;
+            }
+        };
+        // Editors often replace the file instead of writing it, so watch the dir
+        let Some(dir) = config_path.parent() else {
+            return;
+        };
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            warn!("Failed to watch config: {}", e);
+            return;
+        }
+        while let Ok(event) = receiver.recv() {
+            let is_config = match event {
+                Ok(event) => {
+                    !event.kind.is_access()
+                        && event
+                            .paths
+                            .iter()
+                            .any(|path| path.file_name() == config_path.file_name())
+                }
+                Err(_) => false,
+            };
+            if !is_config {
+                continue;
+            }
+            // Wait for the writes to settle
+            while receiver
+                .recv_timeout(std::time::Duration::from_millis(300))
+                .is_ok()
+            {}
+            reload_config(&config_path);
+        }
+    });
+}
+
+fn reload_config(config_path: &std::path::Path) {
+    // Half written files are read again on the next change
+    let new: Map<String, Value> = match std::fs::read(config_path)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+    {
+        Some(v) => v,
+        None => return,
+    };
+    let old = stored_entries();
+    // Our own saves and the ones of the windows end here
+    if old == new {
+        return;
+    }
+    info!("Config changed on disk, reloading");
+    // Sealed before locking the store, the keyring can be slow
+    let mut sealed = Map::new();
+    for (key, value) in &new {
+        let value = match secret::seal(key, value.clone()) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("Failed to store secrets of {}: {}", key, e);
+                value.clone()
+            }
+        };
+        sealed.insert(key.clone(), value);
+    }
+    let state = APP.get().unwrap().state::<StoreWrapper>();
+    let mut store = state.0.lock().unwrap();
+    let _ = store.clear();
+    for (key, value) in &sealed {
+        let _ = store.insert(key.clone(), value.clone());
+    }
+    // Plain secrets written by hand are moved out of the file
+    if sealed != new {
+        if let Err(e) = store.save() {
+            warn!("Failed to save config: {:?}", e);
+        }
+    }
+    drop(store);
+    apply_changes(&old, &sealed, false);
+}
+
+// Pick up a config.json replaced as a whole, e.g. by restoring a backup
+pub fn reload_config_store() {
+    let app_handle = APP.get().unwrap();
+    let Some(dir) = config_dir() else {
+        return;
+    };
+    let config_path = dir
+        .join(app_handle.config().tauri.bundle.identifier.clone())
+        .join("config.json");
+    reload_config(&config_path);
+    // The config may be from before the latest migrations
+    let state = app_handle.state::<StoreWrapper>();
+    migrate(&mut state.0.lock().unwrap(), &config_path);
+    let _ = check_service_available();
+}
+
+#[tauri::command]
+pub fn get_config_overrides() -> Map<String, Value> {
+    overrides()
+}
+
+#[tauri::command]
+pub fn get_config_source(key: &str) -> Option<ConfigSource> {
+    get_with_source(key).map(|(_, source)| source)
+}
+
+#[tauri::command]
+pub fn get_profiles() -> Value {
+    json!({ "active": active_profile(), "profiles": list_profiles() })
+}
+
+#[tauri::command(async)]
+pub async fn set_profile(name: &str) -> Result<(), Error> {
+    switch_profile(name)
+}
+
+#[tauri::command(async)]
+pub async fn remove_profile(name: &str) -> Result<(), Error> {
+    delete_profile(name)
+}
+
+#[tauri::command(async)]
+pub async fn export_config(path: &str, sections: Vec<String>) -> Result<usize, Error> {
+    export_sections(path, &sections)
+}
+
+#[tauri::command(async)]
+pub async fn preview_import_config(path: &str) -> Result<Value, Error> {
+    let sections: Vec<&str> = read_export(path)?
+        .into_iter()
+        .map(|(section, _)| section)
+        .collect();
+    let all = CONFIG_SECTIONS.map(String::from);
+    let changes: Vec<ImportChange> = plan_import(path, &all)?
+        .into_iter()
+        .map(|(change, _)| change)
+        .collect();
+    Ok(json!({ "sections": sections, "changes": changes }))
+}
+
+#[tauri::command(async)]
+pub async fn import_config(
+    path: &str,
+    sections: Vec<String>,
+    keep: Vec<String>,
+) -> Result<usize, Error> {
+    import_sections(path, &sections, &keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_secret_key_finds_passwords_tokens_and_keys() {
+        for key in [
+            "webdav_password",
+            "server_token",
+            "s3_secret_key",
+            "apiKey",
+            "access-key",
+        ] {
+            assert!(is_secret_key(key), "{key}");
+        }
+        for key in [
+            "hotkey_selection_translate",
+            "translate_source_language",
+            "url",
+        ] {
+            assert!(!is_secret_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn mask_secrets_hides_nested_secrets() {
+        let value = json!({
+            "url": "https://example.com",
+            "apiKey": "abc",
+            "token": "",
+            "nested": { "password": "p" }
+        });
+        assert_eq!(
+            mask_secrets("deepl@abc", &value),
+            json!({
+                "url": "https://example.com",
+                "apiKey": SECRET_MASK,
+                "token": "",
+                "nested": { "password": SECRET_MASK }
+            })
+        );
+        assert_eq!(
+            mask_secrets("server_token", &json!("t")),
+            json!(SECRET_MASK)
+        );
+        assert_eq!(mask_secrets("server_port", &json!(60828)), json!(60828));
+    }
+
+    #[test]
+    fn unmask_secrets_keeps_the_stored_secrets() {
+        let old = json!({ "apiKey": "abc", "url": "https://old.example.com" });
+        let value = json!({ "apiKey": SECRET_MASK, "url": "https://example.com" });
+        assert_eq!(
+            unmask_secrets(value, Some(&old)),
+            json!({ "apiKey": "abc", "url": "https://example.com" })
+        );
+        // Nothing to keep, the mask is written as it is
+        assert_eq!(unmask_secrets(json!(SECRET_MASK), None), json!(SECRET_MASK));
+        assert_eq!(
+            unmask_secrets(json!("new"), Some(&json!("old"))),
+            json!("new")
+        );
+    }
 }
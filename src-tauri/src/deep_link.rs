@@ -0,0 +1,168 @@
+// `pot://translate?text=hello&to=ja`, `pot://ocr` and `pot://config`
+// The system starts pot with the url as argument, single instance sends it to the running one
+use crate::window::*;
+use log::{info, warn};
+use std::collections::HashMap;
+use url::Url;
+
+const SCHEME: &str = "pot";
+const MAX_TEXT_LENGTH: usize = 10000;
+
+// Find the url in the arguments and run it, returns false if there is none
+pub fn handle_args(args: &[String]) -> bool {
+    match args.iter().find(|v| v.starts_with("pot://")) {
+        Some(url) => {
+            if let Err(e) = handle_url(url) {
+                warn!("Invalid deep link {}: {}", url, e);
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+fn handle_url(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Unknown scheme: {}", url.scheme()));
+    }
+    let mut query: HashMap<String, String> = HashMap::new();
+    for (key, value) in url.query_pairs() {
+        // `from` and `to` read better in links
+        let key = match key.as_ref() {
+            "from" => "source".to_string(),
+            "to" => "target".to_string(),
+            _ => key.to_string(),
+        };
+        query.insert(key, value.to_string());
+    }
+    let mut options = WindowOptions::from_query(&query)?;
+    // `pot://translate` has the action as host, `pot:translate` as path
+    let action = url
+        .host_str()
+        .unwrap_or(url.path())
+        .trim_matches('/')
+        .to_string();
+    options.check_services(match action.as_str() {
+        "ocr" | "ocr_recognize" => "recognize",
+        _ => "translate",
+    })?;
+    info!("Handle deep link: {}", action);
+    match action.as_str() {
+        "translate" => match query.get("text").map(|v| v.trim()) {
+            Some(text) if text.chars().count() > MAX_TEXT_LENGTH => {
+                return Err("Text is too long".to_string())
+            }
+            Some(text) if !text.is_empty() => {
+                set_window_options("translate", options);
+                text_translate(text.to_string());
+            }
+            _ => {
+                set_window_options("translate", options);
+                input_translate();
+            }
+        },
+        "ocr" | "ocr_recognize" => confirm_screenshot(move || {
+            set_window_options("recognize", options);
+            ocr_recognize();
+        }),
+        "ocr_translate" => confirm_screenshot(move || {
+            set_window_options("translate", options);
+            ocr_translate();
+        }),
+        "config" => config_window(),
+        _ => return Err(format!("Unknown action: {action}")),
+    }
+    Ok(())
+}
+
+// Any web page can open a `pot://` link, so the screen is only captured once the user agrees
+fn confirm_screenshot(action: impl FnOnce() + Send + 'static) {
+    tauri::api::dialog::ask(
+        None::<&tauri::Window>,
+        "Pot",
+        "A link wants to take a screenshot for OCR, continue?",
+        move |confirmed| match confirmed {
+            true => action(),
+            false => info!("Deep link screenshot cancelled"),
+        },
+    );
+}
+
+// Make the system open `pot://` links with this executable
+// macOS passes urls with an Apple Event instead of the arguments, which tauri 1 can't receive
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub fn register_scheme() {
+    let exe = match std::env::current_exe() {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Failed to get executable path: {}", e);
+            return;
+        }
+    };
+    match register(exe) {
+        Ok(true) => info!("Registered {}:// url scheme", SCHEME),
+        Ok(false) => {}
+        Err(e) => warn!("Failed to register {}:// url scheme: {}", SCHEME, e),
+    }
+}
+
+// Only writes the handler when it is missing or points elsewhere, returns whether it did
+#[cfg(target_os = "linux")]
+fn register(exe: std::path::PathBuf) -> Result<bool, std::io::Error> {
+    // The executable of an AppImage is mounted somewhere else on every start
+    let exe = std::env::var("APPIMAGE")
+        .map(std::path::PathBuf::from)
+        .unwrap_or(exe);
+    let Some(dir) = dirs::data_dir() else {
+        return Ok(false);
+    };
+    let dir = dir.join("applications");
+    let path = dir.join("pot-url-handler.desktop");
+    let content = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Pot\n\
+         Exec=\"{}\" %u\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/{SCHEME};\n",
+        exe.display()
+    );
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(content.as_str()) {
+        return Ok(false);
+    }
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, content)?;
+    std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            "pot-url-handler.desktop",
+            &format!("x-scheme-handler/{SCHEME}"),
+        ])
+        .status()?;
+    Ok(true)
+}
+
+#[cfg(target_os = "windows")]
+fn register(exe: std::path::PathBuf) -> Result<bool, std::io::Error> {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+    let path = format!("Software\\Classes\\{SCHEME}");
+    let open_command = format!("\"{}\" \"%1\"", exe.display());
+    let current: Option<String> = hkcu
+        .open_subkey(format!("{path}\\shell\\open\\command"))
+        .and_then(|key| key.get_value(""))
+        .ok();
+    if current.as_deref() == Some(open_command.as_str()) {
+        return Ok(false);
+    }
+    let (key, _) = hkcu.create_subkey(&path)?;
+    key.set_value("", &format!("URL:{SCHEME}"))?;
+    key.set_value("URL Protocol", &"")?;
+    let (command, _) = key.create_subkey("shell\\open\\command")?;
+    command.set_value("", &open_command)?;
+    Ok(true)
+}
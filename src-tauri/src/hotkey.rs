@@ -1,4 +1,4 @@
-use crate::config::{get, set};
+use crate::config::AppConfig;
 use crate::window::{input_translate, ocr_recognize, ocr_translate, selection_translate};
 use crate::APP;
 use log::{info, warn};
@@ -8,18 +8,10 @@ fn register<F>(app_handle: &AppHandle, name: &str, handler: F, key: &str) -> Res
 where
     F: Fn() + Send + 'static,
 {
-    let hotkey = {
-        if key.is_empty() {
-            match get(name) {
-                Some(v) => v.as_str().unwrap().to_string(),
-                None => {
-                    set(name, "");
-                    String::new()
-                }
-            }
-        } else {
-            key.to_string()
-        }
+    let hotkey = if key.is_empty() {
+        AppConfig::load().hotkey(name).to_string()
+    } else {
+        key.to_string()
     };
 
     if !hotkey.is_empty() {
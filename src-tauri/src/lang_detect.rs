@@ -1,3 +1,8 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether the local detector has been warmed up
+static LANG_DETECT_LOADED: AtomicBool = AtomicBool::new(false);
+
 pub fn init_lang_detect() {
     // https://crates.io/crates/lingua
     use lingua::{Language, LanguageDetectorBuilder};
@@ -27,6 +32,11 @@ pub fn init_lang_detect() {
     ];
     let detector = LanguageDetectorBuilder::from_languages(&languages).build();
     let _ = detector.detect_language_of("Hello Language");
+    LANG_DETECT_LOADED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_lang_detect_loaded() -> bool {
+    LANG_DETECT_LOADED.load(Ordering::SeqCst)
 }
 #[tauri::command]
 pub fn lang_detect(text: &str) -> Result<&str, ()> {
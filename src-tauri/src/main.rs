@@ -1,14 +1,19 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod app_rule;
 mod backup;
+mod cli;
 mod clipboard;
 mod cmd;
 mod config;
+mod deep_link;
 mod error;
 mod hotkey;
 mod lang_detect;
+mod policy;
 mod screenshot;
+mod secret;
 mod server;
 mod system_ocr;
 mod tray;
@@ -23,8 +28,11 @@ use hotkey::*;
 use lang_detect::*;
 use log::{info};
 use once_cell::sync::OnceCell;
+use policy::get_policy;
 use screenshot::screenshot;
+use secret::*;
 use server::*;
+use std::collections::HashMap;
 use std::sync::Mutex;
 use system_ocr::*;
 use tauri::api::notification::Notification;
@@ -33,11 +41,16 @@ use tauri_plugin_log::LogTarget;
 use tray::*;
 use updater::check_update;
 use window::config_window;
+use window::get_window_options;
 use window::updater_window;
+use window::WindowOptionsWrapper;
 
 // Global AppHandle
 pub static APP: OnceCell<tauri::AppHandle> = OnceCell::new();
 
+// Same as `identifier` in tauri.conf.json, for the code that runs before or without the app
+pub const IDENTIFIER: &str = "com.pot-app.desktop";
+
 // Text to be translated
 pub struct StringWrapper(pub Mutex<String>);
 pub struct SelectionInfoWrapper(pub Mutex<Option<SelectionInfo>>);
@@ -53,27 +66,41 @@ pub struct SelectionInfo {
 }
 
 fn main() {
-    tauri::Builder::default()
-        // .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
-        //     info!("Single instance callback triggered");
-        //     match app {
-        //         Some(app) => {
-        //             info!("Single instance detected: {}, {argv:?}, {cwd}", app.package_info().name);
-        //             // 尝试激活已存在的窗口
-        //             if let Some(window) = app.get_window("main") {
-        //                 if let Err(e) = window.set_focus() {
-        //                     warn!("Failed to focus window: {}", e);
-        //                 }
-        //             }
-        //         }
-        //         None => {
-        //             warn!("Single instance detected but app handle is null");
-        //         }
-        //     }
-        // }))
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    config::init_overrides(&args);
+    if let Some(command) = cli::parse(&args) {
+        let command = match command {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("{e}\n\n{}", cli::usage());
+                std::process::exit(2);
+            }
+        };
+        match cli::run(&command) {
+            Some(code) => std::process::exit(code),
+            None => {
+                let _ = cli::LOCAL_COMMAND.set(command);
+            }
+        }
+    }
+    // Keep stdout clean for the output of cli commands
+    let log_targets = if cli::is_headless() {
+        vec![LogTarget::LogDir]
+    } else {
+        vec![LogTarget::LogDir, LogTarget::Stdout]
+    };
+    let mut builder = tauri::Builder::default();
+    // Headless cli commands may run next to the app
+    if !cli::is_headless() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|_app, argv, cwd| {
+            info!("Second instance started in {cwd}: {argv:?}");
+            cli::handle_second_instance(&argv);
+        }));
+    }
+    builder = builder
         .plugin(
             tauri_plugin_log::Builder::default()
-                .targets([LogTarget::LogDir, LogTarget::Stdout])
+                .targets(log_targets)
                 .build(),
         )
         .plugin(tauri_plugin_autostart::init(
@@ -82,8 +109,12 @@ fn main() {
         ))
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_store::Builder::default().build())
-        .plugin(tauri_plugin_fs_watch::init())
-        .system_tray(tauri::SystemTray::new())
+        .plugin(tauri_plugin_fs_watch::init());
+    // Headless cli commands quit when they are done, no tray icon for them
+    if !cli::is_headless() {
+        builder = builder.system_tray(tauri::SystemTray::new());
+    }
+    builder
         .setup(|app| {
             info!("============== Start App ==============");
             #[cfg(target_os = "macos")]
@@ -98,6 +129,10 @@ fn main() {
             // Init Config
             info!("Init Config Store");
             init_config(app);
+            if cli::is_headless() {
+                cli::run_headless(app.handle());
+                return Ok(());
+            }
             // Check First Run
             if is_first_run() {
                 // Open Config Window
@@ -106,6 +141,10 @@ fn main() {
             }
             app.manage(StringWrapper(Mutex::new("".to_string())));
             app.manage(SelectionInfoWrapper(Mutex::new(None)));
+            app.manage(WindowOptionsWrapper {
+                pending: Mutex::new(HashMap::new()),
+                current: Mutex::new(HashMap::new()),
+            });
             app.manage(ClipboardMonitorEnableWrapper(Mutex::new(
                 "false".to_string(),
             )));
@@ -113,6 +152,10 @@ fn main() {
             update_tray(app.app_handle(), "".to_string(), "".to_string());
             // Start http server
             start_server();
+            watch_config();
+            start_backup_scheduler();
+            #[cfg(any(target_os = "linux", target_os = "windows"))]
+            deep_link::register_scheme();
             // Register Global Shortcut
             match register_shortcut("all") {
                 Ok(()) => {}
@@ -123,35 +166,20 @@ fn main() {
                     .show()
                     .unwrap(),
             }
-            match get("proxy_enable") {
-                Some(v) => {
-                    if v.as_bool().unwrap()
-                        && get("proxy_host")
-                            .map_or(false, |host| !host.as_str().unwrap().is_empty())
-                    {
-                        let _ = set_proxy();
-                    }
-                }
-                None => {}
+            let config = AppConfig::load();
+            if config.proxy_enable && !config.proxy_host.is_empty() {
+                let _ = set_proxy();
             }
             // Check Update
             check_update(app.handle());
-            if let Some(engine) = get("translate_detect_engine") {
-                if engine.as_str().unwrap() == "local" {
-                    init_lang_detect();
-                }
+            if config.translate_detect_engine == "local" {
+                init_lang_detect();
             }
-            let clipboard_monitor = match get("clipboard_monitor") {
-                Some(v) => v.as_bool().unwrap(),
-                None => {
-                    set("clipboard_monitor", false);
-                    false
-                }
-            };
             app.manage(ClipboardMonitorEnableWrapper(Mutex::new(
-                clipboard_monitor.to_string(),
+                config.clipboard_monitor.to_string(),
             )));
             start_clipboard_monitor(app.handle());
+            cli::run_startup();
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -176,7 +204,25 @@ fn main() {
             install_plugin,
             font_list,
             aliyun,
+            s3,
+            undo_restore,
             replace_selected_text,
+            api_result,
+            get_window_options,
+            set_secret,
+            get_secret,
+            remove_secret,
+            get_config_overrides,
+            get_config_source,
+            seal_config,
+            open_config,
+            get_profiles,
+            set_profile,
+            remove_profile,
+            get_policy,
+            export_config,
+            preview_import_config,
+            import_config,
         ])
         .on_system_tray_event(tray_event_handler)
         .build(tauri::generate_context!())
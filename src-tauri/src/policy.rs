@@ -0,0 +1,98 @@
+// Read-only policy shipped by administrators, it wins over everything the user sets
+// {
+//     "locked": { "check_update": false, "server_enable": false },
+//     "services": { "translate": ["deepl", "google"] },
+//     "disable_plugins": true
+// }
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Policy {
+    // Keys with the value they are forced to
+    pub locked: Map<String, Value>,
+    // Allowed service names for recognize, translate, tts and collection, all if missing
+    pub services: HashMap<String, Vec<String>>,
+    pub disable_plugins: bool,
+}
+
+// Only the system location, users must not be able to point pot at another file
+fn policy_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let dir = std::env::var("ProgramData").unwrap_or("C:\\ProgramData".to_string());
+        PathBuf::from(dir).join("pot").join("policy.json")
+    }
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support/pot/policy.json")
+    }
+    #[cfg(target_os = "linux")]
+    {
+        PathBuf::from("/etc/pot/policy.json")
+    }
+}
+
+pub static POLICY: Lazy<Policy> = Lazy::new(|| {
+    let path = policy_path();
+    let content = match std::fs::read_to_string(&path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Policy::default(),
+        Err(e) => {
+            warn!("Failed to read policy {:?}: {}", path, e);
+            return Policy::default();
+        }
+    };
+    match serde_json::from_str::<Policy>(&content) {
+        Ok(policy) => {
+            info!("Policy loaded from {:?}", path);
+            policy
+        }
+        Err(e) => {
+            warn!("Invalid policy {:?}: {}", path, e);
+            Policy::default()
+        }
+    }
+});
+
+pub fn locked_value(key: &str) -> Option<&'static Value> {
+    POLICY.locked.get(key)
+}
+
+pub fn is_locked(key: &str) -> bool {
+    POLICY.locked.contains_key(key)
+}
+
+// `kind` is recognize, translate, tts or collection, `name` the service without the instance id
+pub fn is_service_allowed(kind: &str, name: &str) -> bool {
+    if POLICY.disable_plugins && name.starts_with("plugin") {
+        return false;
+    }
+    match POLICY.services.get(kind) {
+        Some(allowed) => allowed.iter().any(|v| v == name),
+        None => true,
+    }
+}
+
+// Services passed by a caller (api, cli, deep link, app rule) go through the same allowlist,
+// an error if there were some and none of them is allowed
+pub fn allowed_services(kind: &str, services: Vec<String>) -> Result<Vec<String>, String> {
+    let requested = services.len();
+    let allowed: Vec<String> = services
+        .into_iter()
+        .filter(|service| is_service_allowed(kind, service.split('@').next().unwrap_or_default()))
+        .collect();
+    if requested > 0 && allowed.is_empty() {
+        return Err("Service not allowed by policy".to_string());
+    }
+    Ok(allowed)
+}
+
+#[tauri::command]
+pub fn get_policy() -> &'static Policy {
+    &POLICY
+}
@@ -0,0 +1,258 @@
+// Api keys and passwords are kept out of config.json, the config only has a `secret://<id>` reference
+// The value is in the system keyring, or in an encrypted file where there is none
+use crate::config::{is_secret_key, SECRET_MASK};
+use crate::error::Error;
+use crate::IDENTIFIER;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use log::warn;
+use rand::RngCore;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const REFERENCE_PREFIX: &str = "secret://";
+const NONCE_LENGTH: usize = 12;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+// Keyring reads are slow and may ask the user, each secret is read once
+static CACHE: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+// Store a secret and get the reference to put in the config, an empty value removes it
+pub fn store_secret(id: &str, value: &str) -> Result<String, Error> {
+    if value.is_empty() {
+        delete_secret(id)?;
+        return Ok(String::new());
+    }
+    match keyring::Entry::new(IDENTIFIER, id).and_then(|entry| entry.set_password(value)) {
+        // Don't leave an old copy in the file
+        Ok(()) => file_set(id, None)?,
+        Err(e) => {
+            warn!("Keyring unavailable, store secret {} in file: {}", id, e);
+            file_set(id, Some(value))?;
+        }
+    }
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), value.to_string());
+    Ok(format!("{REFERENCE_PREFIX}{id}"))
+}
+
+// A missing secret is empty, e.g. after restoring a backup on another machine
+pub fn fetch_secret(reference: &str) -> Result<String, Error> {
+    let id = reference
+        .strip_prefix(REFERENCE_PREFIX)
+        .unwrap_or(reference);
+    if let Some(v) = CACHE.lock().unwrap().get(id) {
+        return Ok(v.clone());
+    }
+    let value = match keyring::Entry::new(IDENTIFIER, id).and_then(|entry| entry.get_password()) {
+        Ok(v) => v,
+        Err(keyring::Error::NoEntry) => file_get(id)?.unwrap_or_default(),
+        Err(e) => {
+            warn!("Keyring unavailable, read secret {} from file: {}", id, e);
+            file_get(id)?.unwrap_or_default()
+        }
+    };
+    CACHE.lock().unwrap().insert(id.to_string(), value.clone());
+    Ok(value)
+}
+
+// A config value that may be a reference, for the fields `AppConfig` doesn't resolve
+pub fn reveal(value: &str) -> String {
+    match value.starts_with(REFERENCE_PREFIX) {
+        true => fetch_secret(value).unwrap_or_default(),
+        false => value.to_string(),
+    }
+}
+
+pub fn delete_secret(id: &str) -> Result<(), Error> {
+    let id = id.strip_prefix(REFERENCE_PREFIX).unwrap_or(id);
+    CACHE.lock().unwrap().remove(id);
+    match keyring::Entry::new(IDENTIFIER, id).and_then(|entry| entry.delete_password()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => warn!("Failed to delete secret {} from keyring: {}", id, e),
+    }
+    file_set(id, None)
+}
+
+// Move the plain secrets of a config value to the secret storage
+pub fn seal(key: &str, value: Value) -> Result<Value, Error> {
+    seal_value(key, key, value)
+}
+
+// Same as `seal`, with the secrets kept apart from the ones of the live config
+pub fn seal_in(scope: &str, key: &str, value: Value) -> Result<Value, Error> {
+    seal_value(&format!("{scope}/{key}"), key, value)
+}
+
+fn seal_value(id: &str, name: &str, value: Value) -> Result<Value, Error> {
+    match value {
+        Value::String(v)
+            if is_secret_key(name) && !v.starts_with(REFERENCE_PREFIX) && v != SECRET_MASK =>
+        {
+            Ok(Value::String(store_secret(id, &v)?))
+        }
+        Value::Object(map) => {
+            let mut sealed = Map::new();
+            for (k, v) in map {
+                let v = seal_value(&format!("{id}.{k}"), &k, v)?;
+                sealed.insert(k, v);
+            }
+            Ok(Value::Object(sealed))
+        }
+        _ => Ok(value),
+    }
+}
+
+// Replace the references in a config value with the secrets
+pub fn open(value: Value) -> Value {
+    match value {
+        Value::String(v) if v.starts_with(REFERENCE_PREFIX) => match fetch_secret(&v) {
+            Ok(v) => Value::String(v),
+            Err(e) => {
+                warn!("Failed to read secret {}: {}", v, e);
+                Value::String(String::new())
+            }
+        },
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, open(v))).collect()),
+        _ => value,
+    }
+}
+
+// The key is in the local data dir, so it is neither roamed nor backed up with the config
+fn secret_dir() -> Result<PathBuf, Error> {
+    let dir = dirs::data_local_dir()
+        .ok_or(Error::Error("Get Data Dir Failed".into()))?
+        .join(IDENTIFIER);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// The key sits next to secrets.json, so the file fallback only keeps secrets from casual reads
+// (a synced or shared config dir, a glance at the file). Anyone who can read the data dir has both,
+// the keyring is what actually protects them
+fn cipher() -> Result<Aes256Gcm, Error> {
+    let path = secret_dir()?.join("secret.key");
+    // A new key makes every stored secret unreadable, only create one if there is none
+    let key = match std::fs::read(&path) {
+        Ok(v) if v.len() == 32 => v,
+        Ok(_) => return Err(Error::Error("Invalid Secret Key File".into())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => create_key(&path)?,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+// Never readable by others, not even for a moment
+fn create_key(path: &Path) -> Result<Vec<u8>, Error> {
+    let mut key = vec![0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(path) {
+        Ok(mut file) => {
+            file.write_all(&key)?;
+            file.sync_all()?;
+            Ok(key)
+        }
+        // Created by another thread in the meantime
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => match std::fs::read(path)? {
+            v if v.len() == 32 => Ok(v),
+            _ => Err(Error::Error("Invalid Secret Key File".into())),
+        },
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read_file() -> Result<Map<String, Value>, Error> {
+    match std::fs::read_to_string(secret_dir()?.join("secrets.json")) {
+        Ok(v) => Ok(serde_json::from_str(&v)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Map::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn file_get(id: &str) -> Result<Option<String>, Error> {
+    let _lock = FILE_LOCK.lock().unwrap();
+    let Some(Value::String(data)) = read_file()?.remove(id) else {
+        return Ok(None);
+    };
+    let data = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|e| Error::Error(e.to_string().into()))?;
+    if data.len() < NONCE_LENGTH {
+        return Err(Error::Error("Invalid Secret".into()));
+    }
+    let (nonce, data) = data.split_at(NONCE_LENGTH);
+    let plain = cipher()?
+        .decrypt(Nonce::from_slice(nonce), data)
+        .map_err(|_| Error::Error("Decrypt Secret Failed".into()))?;
+    Ok(Some(String::from_utf8_lossy(&plain).to_string()))
+}
+
+fn file_set(id: &str, value: Option<&str>) -> Result<(), Error> {
+    let _lock = FILE_LOCK.lock().unwrap();
+    let mut secrets = read_file()?;
+    match value {
+        Some(value) => {
+            let mut nonce = [0u8; NONCE_LENGTH];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            let mut data = nonce.to_vec();
+            data.extend(
+                cipher()?
+                    .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+                    .map_err(|_| Error::Error("Encrypt Secret Failed".into()))?,
+            );
+            secrets.insert(
+                id.to_string(),
+                Value::String(general_purpose::STANDARD.encode(data)),
+            );
+        }
+        None => {
+            if secrets.remove(id).is_none() {
+                return Ok(());
+            }
+        }
+    }
+    std::fs::write(
+        secret_dir()?.join("secrets.json"),
+        serde_json::to_string_pretty(&secrets)?,
+    )?;
+    Ok(())
+}
+
+#[tauri::command(async)]
+pub async fn set_secret(id: &str, value: &str) -> Result<String, Error> {
+    store_secret(id, value)
+}
+
+#[tauri::command(async)]
+pub async fn get_secret(reference: &str) -> Result<String, Error> {
+    fetch_secret(reference)
+}
+
+#[tauri::command(async)]
+pub async fn remove_secret(id: &str) -> Result<(), Error> {
+    delete_secret(id)
+}
+
+// Used by the frontend store, so windows only see the secrets and config.json only the references
+#[tauri::command(async)]
+pub async fn seal_config(key: &str, value: Value) -> Result<Value, Error> {
+    seal(key, value)
+}
+
+#[tauri::command(async)]
+pub fn open_config(value: Value) -> Value {
+    open(value)
+}
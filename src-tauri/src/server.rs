@@ -1,51 +1,327 @@
-use crate::config::{get, set};
+use crate::clipboard::ClipboardMonitorEnableWrapper;
+use crate::config::{
+    active_profile, apply_changes, delete_profile, get, get_plugin_list, get_with_source,
+    list_profiles, mask_secrets, set, stored_entries, switch_profile, unmask_secrets, AppConfig,
+    ConfigSource,
+};
+use crate::error::Error;
+use crate::hotkey::register_shortcut_by_frontend;
+use crate::lang_detect::is_lang_detect_loaded;
+use crate::policy::{allowed_services, is_locked, is_service_allowed};
 use crate::window::*;
 use log::{info, warn};
-use std::thread;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use tauri::api::notification;
-use tiny_http::{Request, Response, Server};
+use tauri::{GlobalShortcutManager, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+use url::Url;
+
+// How many ports after `server_port` to try when it is taken
+const PORT_RETRY: u16 = 20;
+// Clients listening on `/api/events`
+static SUBSCRIBERS: Lazy<Mutex<Vec<Sender<String>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// Api requests waiting for the daemon window to send back a result
+static PENDING: Lazy<Mutex<HashMap<String, Sender<Value>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+static SERVERS: Mutex<Vec<Arc<Server>>> = Mutex::new(Vec::new());
+static SERVER_THREADS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+static FORWARD_RESULTS: Once = Once::new();
+const MAX_IMAGE_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(serde::Deserialize)]
+struct TranslateRequest {
+    text: String,
+    source: Option<String>,
+    target: Option<String>,
+    services: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+struct OcrRequest {
+    image: Option<String>,
+    service: Option<String>,
+    lang: Option<String>,
+    translate: Option<bool>,
+    target: Option<String>,
+}
 
 pub fn start_server() {
-    let port = match get("server_port") {
-        Some(v) => v.as_i64().unwrap(),
-        None => {
-            set("server_port", 60828);
-            60828
+    let config = AppConfig::load();
+    if !config.server_enable {
+        info!("Server is disabled");
+        return;
+    }
+    let port = config.server_port;
+    let address = config.server_address;
+    if config.server_token.is_empty() {
+        set("server_token", generate_token());
+    }
+    if !is_loopback(&address) && !config.server_auth {
+        warn!("Server is listening on {address} without a token, only requests to localhost are accepted");
+    }
+    // Results are emitted by the translate/recognize windows and the daemon window
+    // Only listen once, the server is started again when its config changes
+    FORWARD_RESULTS.call_once(|| {
+        let app_handle = crate::APP.get().unwrap();
+        for event in ["translate_result", "recognize_result"] {
+            app_handle.listen_global(event, move |e| {
+                let data = e
+                    .payload()
+                    .and_then(|v| serde_json::from_str(v).ok())
+                    .unwrap_or(Value::Null);
+                broadcast(event, data);
+            });
         }
-    };
-    thread::spawn(move || {
-        let server = match Server::http(format!("127.0.0.1:{port}")) {
-            Ok(v) => v,
-            Err(e) => {
-                let _ = notification::Notification::new("com.pot-spp.com")
-                    .title("Server start failed")
-                    .body("Please Change Server Port and restart the application")
-                    .show();
-                warn!("Server start failed: {}", e);
-                return;
+    });
+    let mut servers = vec![];
+    #[cfg(unix)]
+    {
+        let path = config.server_unix_socket;
+        if !path.is_empty() {
+            match bind_unix(&path, config.server_unix_socket_mode) {
+                Ok(server) => {
+                    info!("Server listening on {path}");
+                    servers.push(server);
+                }
+                Err(e) => warn!("Failed to listen on {path}: {e}"),
             }
+        }
+    }
+    match bind_tcp(&address, port) {
+        Ok(server) => servers.push(server),
+        Err(e) => {
+            let _ = notification::Notification::new("com.pot-spp.com")
+                .title("Server start failed")
+                .body("Please Change Server Port and restart the application")
+                .show();
+            warn!("Server start failed: {}", e);
+        }
+    }
+    for server in servers {
+        let server = Arc::new(server);
+        SERVERS.lock().unwrap().push(server.clone());
+        let handle = thread::spawn(move || serve(&server));
+        SERVER_THREADS.lock().unwrap().push(handle);
+    }
+}
+
+// Stop listening and wait for the listeners to close, requests being handled still finish
+pub fn stop_server() {
+    for server in SERVERS.lock().unwrap().drain(..) {
+        server.unblock();
+    }
+    for handle in SERVER_THREADS.lock().unwrap().drain(..) {
+        let _ = handle.join();
+    }
+}
+
+pub fn restart_server() {
+    info!("Restart server");
+    stop_server();
+    start_server();
+}
+
+fn serve(server: &Server) {
+    for request in server.incoming_requests() {
+        // Api requests wait for the translation result, don't block other requests
+        thread::spawn(move || http_handle(request));
+    }
+}
+
+// Try the next ports if the configured one is taken, and remember the one we got
+// A port locked by policy can't be saved, so it is the only one tried
+fn bind_tcp(address: &str, port: u16) -> Result<Server, String> {
+    let locked = is_locked("server_port");
+    let retry = if locked { 0 } else { PORT_RETRY };
+    let mut error = String::new();
+    for i in 0..=retry {
+        let Some(try_port) = port.checked_add(i) else {
+            break;
         };
-        for request in server.incoming_requests() {
-            http_handle(request);
+        match Server::http((address, try_port)) {
+            Ok(server) => {
+                let real_port = server.server_addr().to_ip().map_or(try_port, |v| v.port());
+                info!("Server listening on {address}:{real_port}");
+                if real_port != port && !locked {
+                    warn!("Port {port} is not available, using {real_port} instead");
+                    set("server_port", real_port);
+                    let app_handle = crate::APP.get().unwrap();
+                    let _ = app_handle.emit_all("server_port_changed", real_port);
+                }
+                return Ok(server);
+            }
+            Err(e) => {
+                warn!("Failed to listen on {address}:{try_port}: {e}");
+                error = e.to_string();
+            }
         }
-    });
+        // Port 0 lets the system choose, no need to retry
+        if port == 0 {
+            break;
+        }
+    }
+    Err(error)
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &str, mode: u32) -> Result<Server, String> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    // Remove the socket left by the last run, but never a regular file
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(format!("{path} exists and is not a socket"));
+        }
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    let server = Server::http_unix(std::path::Path::new(path)).map_err(|e| e.to_string())?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .map_err(|e| e.to_string())?;
+    Ok(server)
+}
+
+fn is_loopback(address: &str) -> bool {
+    match address.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => address == "localhost",
+    }
+}
+
+pub fn generate_token() -> String {
+    use rand::distributions::Alphanumeric;
+    use rand::Rng;
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+fn get_header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+// Block requests from web pages (cross-site or DNS rebinding) and check the bearer token
+fn check_auth(request: &Request) -> Result<(), (u16, &'static str)> {
+    let config = AppConfig::load();
+    let auth_enable = config.server_auth;
+    let host = get_header(request, "Host").unwrap_or_default();
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+    let host_allowed = ["127.0.0.1", "localhost", "[::1]"].contains(&host);
+    // Without a token only localhost is served, whatever the address. The token also stops
+    // DNS rebinding, so any host is fine with it
+    if !host_allowed && !auth_enable {
+        return Err((403, "Forbidden: invalid Host header"));
+    }
+    if let Some(origin) = get_header(request, "Origin") {
+        let allowed = [
+            "tauri://localhost",
+            "https://tauri.localhost",
+            "http://tauri.localhost",
+            "http://localhost:1420",
+        ];
+        if !allowed.contains(&origin) {
+            return Err((403, "Forbidden: cross-origin request"));
+        }
+    }
+    if get_header(request, "Sec-Fetch-Site") == Some("cross-site") {
+        return Err((403, "Forbidden: cross-site request"));
+    }
+
+    if auth_enable {
+        let token = crate::secret::reveal(&config.server_token);
+        let bearer = get_header(request, "Authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|v| v.trim());
+        match bearer {
+            Some(v) if !token.is_empty() && constant_time_eq(v, &token) => {}
+            Some(_) => return Err((401, "Unauthorized: invalid token")),
+            None => return Err((401, "Unauthorized: missing bearer token")),
+        }
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0, |acc, (x, y)| acc | (x ^ y))
+        == 0
 }
 
 fn http_handle(request: Request) {
     info!("Handle {} request", request.url());
-    match request.url() {
-        "/" => handle_translate(request),
+    if let Err((status, message)) = check_auth(&request) {
+        warn!("Reject request {}: {}", request.url(), message);
+        if status == 401 {
+            let header = Header::from_bytes(&b"WWW-Authenticate"[..], &b"Bearer"[..]).unwrap();
+            let response = Response::from_string(json!({ "error": message }).to_string())
+                .with_status_code(status)
+                .with_header(header);
+            let _ = request.respond(response);
+            return;
+        }
+        return response_error(request, status, message);
+    }
+    // Only path and query are sent, give the url a base to parse it
+    let url = match Url::parse(&format!("http://127.0.0.1{}", request.url())) {
+        Ok(v) => v,
+        Err(e) => return response_error(request, 400, &format!("Invalid url: {e}")),
+    };
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let mut options = match WindowOptions::from_query(&query) {
+        Ok(v) => v,
+        Err(e) => return response_error(request, 400, &e),
+    };
+    let kind = match url.path() {
+        "/ocr_recognize" | "/api/ocr" => "recognize",
+        _ => "translate",
+    };
+    if let Err(e) = options.check_services(kind) {
+        return response_error(request, 403, &e);
+    }
+    match url.path() {
+        "/" | "/translate" => handle_translate(request, options),
         "/config" => handle_config(request),
-        "/translate" => handle_translate(request),
-        "/selection_translate" => handle_selection_translate(request),
-        "/input_translate" => handle_input_translate(request),
-        "/ocr_recognize" => handle_ocr_recognize(request),
-        "/ocr_translate" => handle_ocr_translate(request),
-        "/ocr_recognize?screenshot=false" => handle_ocr_recognize(request),
-        "/ocr_translate?screenshot=false" => handle_ocr_translate(request),
-        "/ocr_recognize?screenshot=true" => handle_ocr_recognize(request),
-        "/ocr_translate?screenshot=true" => handle_ocr_translate(request),
-        _ => warn!("Unknown request url: {}", request.url()),
+        "/selection_translate" => handle_selection_translate(request, options),
+        "/input_translate" => handle_input_translate(request, options),
+        "/ocr_recognize" => handle_ocr_recognize(request, options, &query),
+        "/ocr_translate" => handle_ocr_translate(request, options, &query),
+        "/api/translate" => handle_api_translate(request, options),
+        "/api/ocr" => handle_api_ocr(request, options, &query),
+        "/api/events" => handle_api_events(request),
+        "/api/status" => handle_api_status(request),
+        path if path.starts_with("/api/config/") => {
+            let key = path.trim_start_matches("/api/config/").to_string();
+            handle_api_config(request, &key)
+        }
+        "/api/profiles" => handle_api_profiles(request),
+        path if path.starts_with("/api/profiles/") => {
+            let name = path.trim_start_matches("/api/profiles/").to_string();
+            handle_api_profile(request, &name)
+        }
+        _ => {
+            warn!("Unknown request url: {}", request.url());
+            response_error(request, 404, "Not found");
+        }
     }
 }
 
@@ -54,42 +330,504 @@ fn handle_config(request: Request) {
     response_ok(request);
 }
 
-fn handle_translate(mut request: Request) {
+fn handle_translate(mut request: Request, options: WindowOptions) {
     let mut content = String::new();
-    request.as_reader().read_to_string(&mut content).unwrap();
+    if let Err(e) = request.as_reader().read_to_string(&mut content) {
+        return response_error(request, 400, &e.to_string());
+    }
+    set_window_options("translate", options);
     text_translate(content);
     response_ok(request);
 }
 
-fn handle_selection_translate(request: Request) {
+fn handle_selection_translate(request: Request, options: WindowOptions) {
+    set_window_options("translate", options);
     selection_translate();
     response_ok(request);
 }
 
-fn handle_input_translate(request: Request) {
+fn handle_input_translate(request: Request, options: WindowOptions) {
+    set_window_options("translate", options);
     input_translate();
     response_ok(request);
 }
 
-fn handle_ocr_recognize(request: Request) {
-    if request.url().ends_with("false") {
-        recognize_window();
-    } else {
+// Only the ocr endpoints take a screenshot, `screenshot=false` skips it
+fn screenshot_option(query: &HashMap<String, String>) -> Result<bool, String> {
+    match query.get("screenshot").map(|v| v.as_str()) {
+        Some("true") | None => Ok(true),
+        Some("false") => Ok(false),
+        Some(v) => Err(format!("Invalid screenshot: {v}")),
+    }
+}
+
+fn handle_ocr_recognize(request: Request, options: WindowOptions, query: &HashMap<String, String>) {
+    let screenshot = match screenshot_option(query) {
+        Ok(v) => v,
+        Err(e) => return response_error(request, 400, &e),
+    };
+    set_window_options("recognize", options);
+    if screenshot {
         ocr_recognize();
+    } else {
+        recognize_window();
     }
     response_ok(request);
 }
 
-fn handle_ocr_translate(request: Request) {
-    if request.url().ends_with("false") {
-        image_translate();
-    } else {
+fn handle_ocr_translate(request: Request, options: WindowOptions, query: &HashMap<String, String>) {
+    let screenshot = match screenshot_option(query) {
+        Ok(v) => v,
+        Err(e) => return response_error(request, 400, &e),
+    };
+    set_window_options("translate", options);
+    if screenshot {
         ocr_translate();
+    } else {
+        image_translate();
     }
     response_ok(request);
 }
 
+fn handle_api_translate(mut request: Request, options: WindowOptions) {
+    if request.method() != &Method::Post {
+        return response_error(request, 405, "Method not allowed");
+    }
+    let mut content = String::new();
+    if let Err(e) = request.as_reader().read_to_string(&mut content) {
+        return response_error(request, 400, &e.to_string());
+    }
+    let body: TranslateRequest = match serde_json::from_str(&content) {
+        Ok(v) => v,
+        Err(e) => return response_error(request, 400, &format!("Invalid request body: {e}")),
+    };
+    if body.text.trim().is_empty() {
+        return response_error(request, 400, "Text is empty");
+    }
+    match api_translate(
+        body.text,
+        body.source.or(options.source),
+        body.target.or(options.target),
+        body.services.or(options.services),
+    ) {
+        Ok(result) => response_json(request, 200, result),
+        Err((status, message)) => response_error(request, status, &message),
+    }
+}
+
+// Translate text with the given services in the daemon window
+pub fn api_translate(
+    text: String,
+    source: Option<String>,
+    target: Option<String>,
+    services: Option<Vec<String>>,
+) -> Result<Value, (u16, String)> {
+    let services = match services {
+        Some(v) => Some(allowed_services("translate", v).map_err(|e| (403, e))?),
+        None => None,
+    };
+    let mut payload = translate_params(source, target, services);
+    if payload["services"].as_array().is_none_or(|v| v.is_empty()) {
+        return Err((400, "No translate service available".to_string()));
+    }
+    payload["text"] = json!(text);
+    match daemon_call("api_translate", payload) {
+        Some(result) => match result.get("error").and_then(|e| e.as_str()) {
+            Some(e) => Err((500, e.to_string())),
+            None => Ok(result),
+        },
+        None => Err((504, "Translate timeout".to_string())),
+    }
+}
+
+fn handle_api_ocr(mut request: Request, options: WindowOptions, query: &HashMap<String, String>) {
+    use base64::{engine::general_purpose, Engine as _};
+    use std::io::Read;
+
+    if request.method() != &Method::Post {
+        return response_error(request, 405, "Method not allowed");
+    }
+    let is_json =
+        get_header(&request, "Content-Type").is_some_and(|v| v.starts_with("application/json"));
+    let mut content = Vec::new();
+    if let Err(e) = request
+        .as_reader()
+        .take(MAX_IMAGE_SIZE + 1)
+        .read_to_end(&mut content)
+    {
+        return response_error(request, 400, &e.to_string());
+    }
+    if content.len() as u64 > MAX_IMAGE_SIZE {
+        return response_error(request, 413, "Image too large");
+    }
+
+    let mut ocr = OcrRequest {
+        image: None,
+        service: options.services.and_then(|v| v.into_iter().next()),
+        lang: options.lang,
+        translate: query.get("translate").map(|v| v == "true"),
+        target: options.target,
+    };
+    let data = if is_json {
+        let body: OcrRequest = match serde_json::from_slice(&content) {
+            Ok(v) => v,
+            Err(e) => return response_error(request, 400, &format!("Invalid request body: {e}")),
+        };
+        let image = body.image.unwrap_or_default();
+        // Accept data url as well as plain base64
+        let image = match image.split_once(";base64,") {
+            Some((_, v)) => v.to_string(),
+            None => image,
+        };
+        ocr.service = body.service.or(ocr.service);
+        ocr.lang = body.lang.or(ocr.lang);
+        ocr.translate = body.translate.or(ocr.translate);
+        ocr.target = body.target.or(ocr.target);
+        match general_purpose::STANDARD.decode(image.trim()) {
+            Ok(v) => v,
+            Err(e) => return response_error(request, 400, &format!("Invalid base64 image: {e}")),
+        }
+    } else {
+        content
+    };
+
+    match api_recognize(&data, ocr.service, ocr.lang, ocr.translate, ocr.target) {
+        Ok(result) => response_json(request, 200, result),
+        Err((status, message)) => response_error(request, status, &message),
+    }
+}
+
+// Recognize a PNG or JPEG image in the daemon window
+pub fn api_recognize(
+    data: &[u8],
+    service: Option<String>,
+    lang: Option<String>,
+    translate: Option<bool>,
+    target: Option<String>,
+) -> Result<Value, (u16, String)> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    if let Some(name) = service.as_ref().and_then(|v| v.split('@').next()) {
+        if !is_service_allowed("recognize", name) {
+            return Err((403, "Service not allowed by policy".to_string()));
+        }
+    }
+    let img = match image::guess_format(data) {
+        Ok(image::ImageFormat::Png) | Ok(image::ImageFormat::Jpeg) => {
+            match image::load_from_memory(data) {
+                Ok(v) => v,
+                Err(e) => return Err((400, format!("Invalid image: {e}"))),
+            }
+        }
+        _ => return Err((415, "Only PNG and JPEG images are supported".to_string())),
+    };
+    // System OCR reads the image from disk and only supports PNG on Windows
+    let mut image_path = match dirs::cache_dir() {
+        Some(v) => v,
+        None => return Err((500, "Get Cache Dir Failed".to_string())),
+    };
+    image_path.push(&crate::APP.get().unwrap().config().tauri.bundle.identifier);
+    if let Err(e) = std::fs::create_dir_all(&image_path) {
+        return Err((500, e.to_string()));
+    }
+    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    image_path.push(format!("pot_api_ocr_{id}.png"));
+    let mut png = std::io::Cursor::new(Vec::new());
+    if let Err(e) = img.write_to(&mut png, image::ImageFormat::Png) {
+        return Err((500, e.to_string()));
+    }
+    let png = png.into_inner();
+    if let Err(e) = std::fs::write(&image_path, &png) {
+        return Err((500, e.to_string()));
+    }
+
+    let config = AppConfig::load();
+    let service = match service {
+        Some(v) => v,
+        None => config
+            .recognize_service_list
+            .into_iter()
+            .next()
+            .unwrap_or("system".to_string()),
+    };
+    let translate = match translate {
+        Some(true) => translate_params(None, target, None),
+        _ => Value::Null,
+    };
+    let payload = json!({
+        "base64": general_purpose::STANDARD.encode(&png),
+        "path": image_path.to_string_lossy(),
+        "service": service,
+        "lang": lang.unwrap_or(config.recognize_language),
+        "translate": translate,
+    });
+    let result = daemon_call("api_recognize", payload);
+    let _ = std::fs::remove_file(&image_path);
+    match result {
+        Some(result) => match result.get("error").and_then(|e| e.as_str()) {
+            Some(e) => Err((500, e.to_string())),
+            None => Ok(result),
+        },
+        None => Err((504, "Recognize timeout".to_string())),
+    }
+}
+
+// Server-sent events stream, the connection is kept open until the client leaves
+fn handle_api_events(request: Request) {
+    use std::io::Write;
+
+    if request.method() != &Method::Get {
+        return response_error(request, 405, "Method not allowed");
+    }
+    let (tx, rx) = channel();
+    SUBSCRIBERS.lock().unwrap().push(tx);
+    // Write the response by hand, tiny_http buffers chunked bodies
+    let mut writer = request.into_writer();
+    let head = "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\n\
+                Connection: keep-alive\r\n\r\n";
+    if writer.write_all(head.as_bytes()).is_err() || writer.flush().is_err() {
+        return;
+    }
+    info!("Event stream client connected");
+    loop {
+        let message = match rx.recv_timeout(Duration::from_secs(15)) {
+            Ok(v) => v,
+            // Keep alive, also finds out disconnected clients
+            Err(RecvTimeoutError::Timeout) => ": ping\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if writer.write_all(message.as_bytes()).is_err() || writer.flush().is_err() {
+            break;
+        }
+    }
+    info!("Event stream client disconnected");
+}
+
+// Send an event to all `/api/events` clients
+pub fn broadcast(event: &str, data: Value) {
+    let message = format!("event: {event}\ndata: {data}\n\n");
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|tx| tx.send(message.clone()).is_ok());
+}
+
+// What this installation supports, so that tools can adapt to it
+fn handle_api_status(request: Request) {
+    if request.method() != &Method::Get {
+        return response_error(request, 405, "Method not allowed");
+    }
+    let app_handle = crate::APP.get().unwrap();
+    let shortcut_manager = app_handle.global_shortcut_manager();
+    let config = AppConfig::load();
+    let mut hotkeys = serde_json::Map::new();
+    for name in [
+        "hotkey_selection_translate",
+        "hotkey_input_translate",
+        "hotkey_ocr_recognize",
+        "hotkey_ocr_translate",
+    ] {
+        let hotkey = config.hotkey(name);
+        let registered =
+            !hotkey.is_empty() && shortcut_manager.is_registered(hotkey).unwrap_or(false);
+        hotkeys.insert(
+            name.to_string(),
+            json!({ "hotkey": hotkey, "registered": registered }),
+        );
+    }
+    let mut plugins = serde_json::Map::new();
+    for plugin_type in ["translate", "recognize", "tts", "collection"] {
+        plugins.insert(
+            plugin_type.to_string(),
+            json!(get_plugin_list(plugin_type).unwrap_or_default()),
+        );
+    }
+    let clipboard_monitor = app_handle
+        .state::<ClipboardMonitorEnableWrapper>()
+        .0
+        .lock()
+        .unwrap()
+        .as_str()
+        == "true";
+
+    response_json(
+        request,
+        200,
+        json!({
+            "version": app_handle.package_info().version.to_string(),
+            "services": {
+                "translate": config.translate_service_list,
+                "recognize": config.recognize_service_list,
+                "tts": config.tts_service_list,
+            },
+            "plugins": plugins,
+            "hotkeys": hotkeys,
+            "clipboard_monitor": clipboard_monitor,
+            "lang_detect_loaded": is_lang_detect_loaded(),
+        }),
+    );
+}
+
+// Read or write a single config value, secrets are masked when read
+fn handle_api_config(mut request: Request, key: &str) {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.@:".contains(c));
+    if !valid {
+        return response_error(request, 400, &format!("Invalid config key: {key}"));
+    }
+    match request.method() {
+        Method::Get => match get_with_source(key) {
+            Some((v, source)) => response_json(
+                request,
+                200,
+                json!({ "key": key, "value": mask_secrets(key, &v), "source": source }),
+            ),
+            None => response_error(request, 404, "Config not found"),
+        },
+        Method::Put => {
+            if is_locked(key) {
+                return response_error(request, 403, &format!("Config {key} is locked by policy"));
+            }
+            let mut content = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut content) {
+                return response_error(request, 400, &format!("Failed to read body: {e}"));
+            }
+            let value: Value = match serde_json::from_str(&content) {
+                Ok(v) => v,
+                Err(e) => return response_error(request, 400, &format!("Invalid JSON: {e}")),
+            };
+            let old = get(key);
+            let value = unmask_secrets(value, old.as_ref());
+            if key.starts_with("hotkey_") {
+                if let Err(e) = apply_hotkey(key, old.as_ref(), &value) {
+                    return response_error(request, 400, &e);
+                }
+            }
+            info!("Set config {} by api", key);
+            let before = stored_entries();
+            set(key, &value);
+            // Same as an import, the proxy, server, clipboard monitor and services follow the change
+            apply_changes(&before, &stored_entries(), false);
+            // An override still wins over the saved value
+            let (value, source) = get_with_source(key).unwrap_or((value, ConfigSource::Stored));
+            response_json(
+                request,
+                200,
+                json!({ "key": key, "value": mask_secrets(key, &value), "source": source }),
+            );
+        }
+        _ => response_error(request, 405, "Method not allowed"),
+    }
+}
+
+fn handle_api_profiles(request: Request) {
+    match request.method() {
+        Method::Get => response_json(
+            request,
+            200,
+            json!({ "active": active_profile(), "profiles": list_profiles() }),
+        ),
+        _ => response_error(request, 405, "Method not allowed"),
+    }
+}
+
+// POST switches to the profile, creating it from the active one if it doesn't exist
+fn handle_api_profile(request: Request, name: &str) {
+    let result = match request.method() {
+        Method::Post => switch_profile(name),
+        Method::Delete => delete_profile(name),
+        _ => return response_error(request, 405, "Method not allowed"),
+    };
+    match result {
+        Ok(()) => response_json(
+            request,
+            200,
+            json!({ "active": active_profile(), "profiles": list_profiles() }),
+        ),
+        Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            response_error(request, 404, "Profile not found")
+        }
+        Err(e) => response_error(request, 400, &e.to_string()),
+    }
+}
+
+// Swap the global shortcut, and get the old one back if the new one can't be registered
+fn apply_hotkey(key: &str, old: Option<&Value>, value: &Value) -> Result<(), String> {
+    let hotkey = value.as_str().ok_or("Hotkey must be a string")?;
+    let old = old.and_then(|v| v.as_str()).unwrap_or_default();
+    let mut shortcut_manager = crate::APP.get().unwrap().global_shortcut_manager();
+    if !old.is_empty() {
+        let _ = shortcut_manager.unregister(old);
+    }
+    if let Err(e) = register_shortcut_by_frontend(key, hotkey) {
+        if !old.is_empty() {
+            let _ = register_shortcut_by_frontend(key, old);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+// Fill the translate parameters that are not given from the config
+fn translate_params(
+    source: Option<String>,
+    target: Option<String>,
+    services: Option<Vec<String>>,
+) -> Value {
+    let config = AppConfig::load();
+    json!({
+        "source": source.unwrap_or(config.translate_source_language),
+        "target": target.unwrap_or(config.translate_target_language),
+        "services": services.unwrap_or(config.translate_service_list),
+    })
+}
+
+// Run a job in the daemon window and wait for it to call `api_result`
+fn daemon_call(event: &str, mut payload: Value) -> Option<Value> {
+    let timeout = AppConfig::load().server_api_timeout;
+    let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst).to_string();
+    let (tx, rx) = channel();
+    PENDING.lock().unwrap().insert(id.clone(), tx);
+    payload["id"] = json!(id);
+    if let Err(e) = get_daemon_window().emit(event, payload) {
+        warn!("Failed to emit {} to daemon window: {}", event, e);
+        PENDING.lock().unwrap().remove(&id);
+        return None;
+    }
+    let result = rx.recv_timeout(Duration::from_secs(timeout)).ok();
+    PENDING.lock().unwrap().remove(&id);
+    result
+}
+
+#[tauri::command]
+pub fn api_result(id: String, result: Value) {
+    match PENDING.lock().unwrap().remove(&id) {
+        Some(tx) => {
+            let _ = tx.send(result);
+        }
+        None => warn!("Api request {} not found, maybe timeout", id),
+    }
+}
+
 fn response_ok(request: Request) {
     let response = Response::from_string("ok");
     request.respond(response).unwrap();
 }
+
+fn response_json(request: Request, status: u16, body: Value) {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to send response: {}", e);
+    }
+}
+
+fn response_error(request: Request, status: u16, message: &str) {
+    response_json(request, status, json!({ "error": message }));
+}
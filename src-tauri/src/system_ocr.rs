@@ -1,17 +1,33 @@
 use dirs::cache_dir;
+use std::path::PathBuf;
+
+// Recognize the given image, or the screenshot if no path is given
+fn image_path(app_handle: &tauri::AppHandle, path: Option<String>) -> PathBuf {
+    match path {
+        Some(v) => PathBuf::from(v),
+        None => {
+            let mut app_cache_dir_path = cache_dir().expect("Get Cache Dir Failed");
+            app_cache_dir_path.push(&app_handle.config().tauri.bundle.identifier);
+            app_cache_dir_path.push("pot_screenshot_cut.png");
+            app_cache_dir_path
+        }
+    }
+}
 
 #[tauri::command(async)]
 #[cfg(target_os = "windows")]
-pub fn system_ocr(app_handle: tauri::AppHandle, lang: &str) -> Result<String, String> {
+pub fn system_ocr(
+    app_handle: tauri::AppHandle,
+    lang: &str,
+    path: Option<String>,
+) -> Result<String, String> {
     use windows::core::HSTRING;
     use windows::Globalization::Language;
     use windows::Graphics::Imaging::BitmapDecoder;
     use windows::Media::Ocr::OcrEngine;
     use windows::Storage::{FileAccessMode, StorageFile};
 
-    let mut app_cache_dir_path = cache_dir().expect("Get Cache Dir Failed");
-    app_cache_dir_path.push(&app_handle.config().tauri.bundle.identifier);
-    app_cache_dir_path.push("pot_screenshot_cut.png");
+    let app_cache_dir_path = image_path(&app_handle, path);
 
     let path = app_cache_dir_path.to_string_lossy().replace("\\\\?\\", "");
 
@@ -62,10 +78,12 @@ pub fn system_ocr(app_handle: tauri::AppHandle, lang: &str) -> Result<String, St
 
 #[tauri::command(async)]
 #[cfg(target_os = "macos")]
-pub fn system_ocr(app_handle: tauri::AppHandle, lang: &str) -> Result<String, String> {
-    let mut app_cache_dir_path = cache_dir().expect("Get Cache Dir Failed");
-    app_cache_dir_path.push(&app_handle.config().tauri.bundle.identifier);
-    app_cache_dir_path.push("pot_screenshot_cut.png");
+pub fn system_ocr(
+    app_handle: tauri::AppHandle,
+    lang: &str,
+    path: Option<String>,
+) -> Result<String, String> {
+    let app_cache_dir_path = image_path(&app_handle, path);
 
     let arch = std::env::consts::ARCH;
     let bin_path = match app_handle
@@ -105,10 +123,12 @@ pub fn system_ocr(app_handle: tauri::AppHandle, lang: &str) -> Result<String, St
 
 #[tauri::command(async)]
 #[cfg(target_os = "linux")]
-pub fn system_ocr(app_handle: tauri::AppHandle, lang: &str) -> Result<String, String> {
-    let mut app_cache_dir_path = cache_dir().expect("Get Cache Dir Failed");
-    app_cache_dir_path.push(&app_handle.config().tauri.bundle.identifier);
-    app_cache_dir_path.push("pot_screenshot_cut.png");
+pub fn system_ocr(
+    app_handle: tauri::AppHandle,
+    lang: &str,
+    path: Option<String>,
+) -> Result<String, String> {
+    let app_cache_dir_path = image_path(&app_handle, path);
     let mut args = ["", ""];
     if lang != "auto" {
         args = ["-l", lang];
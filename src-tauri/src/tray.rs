@@ -1,11 +1,13 @@
 use crate::clipboard::*;
-use crate::config::{get, set};
+use crate::config::{
+    active_profile, list_profiles, set, switch_profile, AppConfig, DEFAULT_PROFILE,
+};
 use crate::window::config_window;
 use crate::window::input_translate;
 use crate::window::ocr_recognize;
 use crate::window::ocr_translate;
 use crate::window::updater_window;
-use log::info;
+use log::{info, warn};
 use tauri::CustomMenuItem;
 use tauri::GlobalShortcutManager;
 use tauri::SystemTrayEvent;
@@ -17,24 +19,13 @@ use tauri::{AppHandle, Manager};
 #[tauri::command]
 pub fn update_tray(app_handle: tauri::AppHandle, mut language: String, mut copy_mode: String) {
     let tray_handle = app_handle.tray_handle();
+    let config = AppConfig::load();
 
     if language.is_empty() {
-        language = match get("app_language") {
-            Some(v) => v.as_str().unwrap().to_string(),
-            None => {
-                set("app_language", "en");
-                "en".to_string()
-            }
-        };
+        language = config.app_language;
     }
     if copy_mode.is_empty() {
-        copy_mode = match get("translate_auto_copy") {
-            Some(v) => v.as_str().unwrap().to_string(),
-            None => {
-                set("translate_auto_copy", "disable");
-                "disable".to_string()
-            }
-        };
+        copy_mode = config.translate_auto_copy;
     }
 
     info!(
@@ -62,17 +53,9 @@ pub fn update_tray(app_handle: tauri::AppHandle, mut language: String, mut copy_
         .set_tooltip(&format!("pot {}", app_handle.package_info().version))
         .unwrap();
 
-    let enable_clipboard_monitor = match get("clipboard_monitor") {
-        Some(v) => v.as_bool().unwrap(),
-        None => {
-            set("clipboard_monitor", false);
-            false
-        }
-    };
-
     tray_handle
         .get_item("clipboard_monitor")
-        .set_selected(enable_clipboard_monitor)
+        .set_selected(config.clipboard_monitor)
         .unwrap();
 
     match copy_mode.as_str() {
@@ -94,6 +77,14 @@ pub fn update_tray(app_handle: tauri::AppHandle, mut language: String, mut copy_
             .unwrap(),
         _ => {}
     }
+
+    // The profile can be switched or removed after the menu was built
+    let profile = tray_handle
+        .try_get_item(&format!("profile:{}", active_profile()))
+        .or_else(|| tray_handle.try_get_item(&format!("profile:{}", DEFAULT_PROFILE)));
+    if let Some(item) = profile {
+        item.set_selected(true).unwrap();
+    }
 }
 
 pub fn tray_event_handler<'a>(app: &'a AppHandle, event: SystemTrayEvent) {
@@ -114,6 +105,7 @@ pub fn tray_event_handler<'a>(app: &'a AppHandle, event: SystemTrayEvent) {
             "view_log" => on_view_log_click(app),
             "restart" => on_restart_click(app),
             "quit" => on_quit_click(app),
+            id if id.starts_with("profile:") => on_profile_click(id.trim_start_matches("profile:")),
             _ => {}
         },
         _ => {}
@@ -122,14 +114,7 @@ pub fn tray_event_handler<'a>(app: &'a AppHandle, event: SystemTrayEvent) {
 
 #[cfg(target_os = "windows")]
 fn on_tray_click() {
-    let event = match get("tray_click_event") {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => {
-            set("tray_click_event", "config");
-            "config".to_string()
-        }
-    };
-    match event.as_str() {
+    match AppConfig::load().tray_click_event.as_str() {
         "config" => config_window(),
         "translate" => input_translate(),
         "ocr_recognize" => ocr_recognize(),
@@ -142,14 +127,7 @@ fn on_input_translate_click() {
     input_translate();
 }
 fn on_clipboard_monitor_click(app: &AppHandle) {
-    let enable_clipboard_monitor = match get("clipboard_monitor") {
-        Some(v) => v.as_bool().unwrap(),
-        None => {
-            set("clipboard_monitor", false);
-            false
-        }
-    };
-    let current = !enable_clipboard_monitor;
+    let current = !AppConfig::load().clipboard_monitor;
     // Update Config File
     set("clipboard_monitor", current);
     // Update State and Start Monitor
@@ -197,12 +175,26 @@ fn on_restart_click(app: &AppHandle) {
     info!("============== Restart App ==============");
     app.restart();
 }
+fn on_profile_click(name: &str) {
+    info!("Switch profile to: {}", name);
+    if let Err(e) = switch_profile(name) {
+        warn!("Failed to switch profile: {}", e);
+    }
+}
 fn on_quit_click(app: &AppHandle) {
     app.global_shortcut_manager().unregister_all().unwrap();
     info!("============== Quit App ==============");
     app.exit(0);
 }
 
+fn profile_submenu(title: &str) -> SystemTraySubmenu {
+    let mut menu = SystemTrayMenu::new();
+    for name in list_profiles() {
+        menu = menu.add_item(CustomMenuItem::new(format!("profile:{name}"), name));
+    }
+    SystemTraySubmenu::new(title, menu)
+}
+
 fn tray_menu_en() -> tauri::SystemTrayMenu {
     let input_translate = CustomMenuItem::new("input_translate", "Input Translate");
     let copy_source = CustomMenuItem::new("copy_source", "Source");
@@ -229,6 +221,7 @@ fn tray_menu_en() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Profile"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -268,6 +261,7 @@ fn tray_menu_zh_cn() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("配置方案"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -307,6 +301,7 @@ fn tray_menu_zh_tw() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("設定檔"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -346,6 +341,7 @@ fn tray_menu_ja() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("プロファイル"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -385,6 +381,7 @@ fn tray_menu_ko() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("프로필"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -425,6 +422,7 @@ fn tray_menu_fr() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Profil"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -463,6 +461,7 @@ fn tray_menu_de() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Profil"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -502,6 +501,7 @@ fn tray_menu_ru() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Профиль"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -541,6 +541,7 @@ fn tray_menu_fa() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("پروفایل"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -581,6 +582,7 @@ fn tray_menu_pt_br() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Perfil"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
@@ -620,6 +622,7 @@ fn tray_menu_uk() -> tauri::SystemTrayMenu {
                 .add_native_item(SystemTrayMenuItem::Separator)
                 .add_item(copy_disable),
         ))
+        .add_submenu(profile_submenu("Профіль"))
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(ocr_recognize)
         .add_item(ocr_translate)
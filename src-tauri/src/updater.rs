@@ -1,16 +1,9 @@
-use crate::config::{get, set};
+use crate::config::AppConfig;
 use crate::window::updater_window;
 use log::{info, warn};
 
 pub fn check_update(app_handle: tauri::AppHandle) {
-    let enable = match get("check_update") {
-        Some(v) => v.as_bool().unwrap(),
-        None => {
-            set("check_update", true);
-            true
-        }
-    };
-    if enable {
+    if AppConfig::load().check_update {
         tauri::async_runtime::spawn(async move {
             match tauri::updater::builder(app_handle).check().await {
                 Ok(update) => {
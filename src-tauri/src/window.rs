@@ -1,10 +1,15 @@
-use crate::config::get;
-use crate::config::set;
+use crate::app_rule::active_rule;
+use crate::config::AppConfig;
+use crate::policy::allowed_services;
+use crate::server::broadcast;
 use crate::SelectionInfo;
 use crate::SelectionInfoWrapper;
 use crate::StringWrapper;
 use crate::APP;
 use log::{info, warn};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tauri::Manager;
 use tauri::Monitor;
 use tauri::Window;
@@ -12,8 +17,136 @@ use tauri::WindowBuilder;
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use window_shadows::set_shadow;
 
+// Overrides for the next translate/recognize window, passed by the http server
+#[derive(Clone, Default, serde::Serialize)]
+pub struct WindowOptions {
+    pub source: Option<String>,
+    pub target: Option<String>,
+    pub services: Option<Vec<String>>,
+    pub lang: Option<String>,
+    // Set by app rules
+    pub auto_copy: Option<String>,
+    #[serde(skip)]
+    pub position: Option<WindowPosition>,
+}
+
+#[derive(Clone)]
+pub enum WindowPosition {
+    Mouse,
+    Center,
+    Fixed(i32, i32),
+}
+
+impl WindowOptions {
+    pub fn from_query(query: &HashMap<String, String>) -> Result<Self, String> {
+        let mut options = WindowOptions::default();
+        for (key, value) in query {
+            match key.as_str() {
+                "source" => options.source = Some(check_language(value)?),
+                "target" => options.target = Some(check_language(value)?),
+                "lang" => options.lang = Some(check_language(value)?),
+                "service" => {
+                    let services: Vec<String> = value
+                        .split(',')
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty())
+                        .collect();
+                    if services.is_empty() {
+                        return Err("Invalid service: empty".to_string());
+                    }
+                    options.services = Some(services);
+                }
+                "position" => {
+                    options.position = Some(match value.as_str() {
+                        "mouse" => WindowPosition::Mouse,
+                        "center" => WindowPosition::Center,
+                        _ => match value.split_once(',') {
+                            Some((x, y)) => match (x.trim().parse(), y.trim().parse()) {
+                                (Ok(x), Ok(y)) => WindowPosition::Fixed(x, y),
+                                _ => return Err(format!("Invalid position: {value}")),
+                            },
+                            None => return Err(format!("Invalid position: {value}")),
+                        },
+                    })
+                }
+                _ => {}
+            }
+        }
+        Ok(options)
+    }
+
+    // `kind` is translate or recognize, the services left are the ones the policy allows
+    pub fn check_services(&mut self, kind: &str) -> Result<(), String> {
+        if let Some(services) = self.services.take() {
+            self.services = Some(allowed_services(kind, services)?);
+        }
+        Ok(())
+    }
+}
+
+fn check_language(lang: &str) -> Result<String, String> {
+    if !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(lang.to_string())
+    } else {
+        Err(format!("Invalid language: {lang}"))
+    }
+}
+
+// Both by window label, requests run on their own threads and one for the recognize window
+// must not hand its options to the translate window
+pub struct WindowOptionsWrapper {
+    pub pending: Mutex<HashMap<String, WindowOptions>>,
+    pub current: Mutex<HashMap<String, WindowOptions>>,
+}
+
+// Options for the next time the `label` window is opened
+pub fn set_window_options(label: &str, options: WindowOptions) {
+    let app_handle = APP.get().unwrap();
+    let state: tauri::State<WindowOptionsWrapper> = app_handle.state();
+    state
+        .pending
+        .lock()
+        .unwrap()
+        .insert(label.to_string(), options);
+}
+
+// Hand the pending options over to the window, hotkeys and tray get the defaults
+fn take_window_options(window: &Window) -> WindowOptions {
+    let app_handle = APP.get().unwrap();
+    let state: tauri::State<WindowOptionsWrapper> = app_handle.state();
+    let mut options = state
+        .pending
+        .lock()
+        .unwrap()
+        .remove(window.label())
+        .unwrap_or_default();
+    // App rules and deep links end up here too, fall back to all services if none is allowed
+    let kind = match window.label() {
+        "recognize" => "recognize",
+        _ => "translate",
+    };
+    if let Err(e) = options.check_services(kind) {
+        warn!("{}: {}", window.label(), e);
+    }
+    state
+        .current
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string(), options.clone());
+    window.emit("window_options", &options).unwrap_or_default();
+    options
+}
+
+#[tauri::command]
+pub fn get_window_options(window: Window) -> WindowOptions {
+    let app_handle = APP.get().unwrap();
+    let state: tauri::State<WindowOptionsWrapper> = app_handle.state();
+    let current = state.current.lock().unwrap();
+    current.get(window.label()).cloned().unwrap_or_default()
+}
+
 // Get daemon window instance
-fn get_daemon_window() -> Window {
+pub fn get_daemon_window() -> Window {
     let app_handle = APP.get().unwrap();
     match app_handle.get_window("daemon") {
         Some(v) => v,
@@ -132,25 +265,15 @@ fn translate_window() -> Window {
         }
     };
     let (window, exists) = build_window("translate", "Translate");
+    let options = take_window_options(&window);
     if exists {
         return window;
     }
     window.set_skip_taskbar(true).unwrap();
+    let config = AppConfig::load();
     // Get Translate Window Size
-    let width = match get("translate_window_width") {
-        Some(v) => v.as_i64().unwrap(),
-        None => {
-            set("translate_window_width", 350);
-            350
-        }
-    };
-    let height = match get("translate_window_height") {
-        Some(v) => v.as_i64().unwrap(),
-        None => {
-            set("translate_window_height", 420);
-            420
-        }
-    };
+    let width = config.translate_window_width;
+    let height = config.translate_window_height;
 
     let monitor = window.current_monitor().unwrap().unwrap();
     let dpi = monitor.scale_factor();
@@ -162,9 +285,11 @@ fn translate_window() -> Window {
         ))
         .unwrap();
 
-    let position_type = match get("translate_window_position") {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => "mouse".to_string(),
+    let position_type = match options.position {
+        Some(WindowPosition::Mouse) => "mouse".to_string(),
+        Some(WindowPosition::Center) => "center".to_string(),
+        Some(WindowPosition::Fixed(..)) => "fixed".to_string(),
+        None => config.translate_window_position,
     };
 
     match position_type.as_str() {
@@ -201,14 +326,14 @@ fn translate_window() -> Window {
                 ))
                 .unwrap();
         }
+        "center" => window.center().unwrap(),
         _ => {
-            let position_x = match get("translate_window_position_x") {
-                Some(v) => v.as_i64().unwrap(),
-                None => 0,
-            };
-            let position_y = match get("translate_window_position_y") {
-                Some(v) => v.as_i64().unwrap(),
-                None => 0,
+            let (position_x, position_y) = match options.position {
+                Some(WindowPosition::Fixed(x, y)) => (x, y),
+                _ => (
+                    config.translate_window_position_x,
+                    config.translate_window_position_y,
+                ),
             };
             window
                 .set_position(tauri::PhysicalPosition::new(
@@ -224,6 +349,12 @@ fn translate_window() -> Window {
 
 pub fn selection_translate() {
     use selection::get_text;
+    // Getting the selection may copy it, so check the rules first
+    let rule = active_rule();
+    if rule.as_ref().is_some_and(|rule| rule.ignore) {
+        info!("Selection translate ignored by app rule");
+        return;
+    }
     // Get Selected Text
     let text = get_text();
     if !text.trim().is_empty() {
@@ -267,8 +398,15 @@ pub fn selection_translate() {
             }
         }
 
+        if let Some(rule) = rule {
+            let state: tauri::State<WindowOptionsWrapper> = app_handle.state();
+            let mut pending = state.pending.lock().unwrap();
+            rule.apply(pending.entry("translate".to_string()).or_default());
+        }
+
         // Create window after saving selection info
         let window = translate_window();
+        broadcast("selection", json!({ "text": text }));
         window.emit("new_text", text).unwrap();
     }
 }
@@ -283,14 +421,11 @@ pub fn input_translate() {
         .unwrap()
         .replace_range(.., "[INPUT_TRANSLATE]");
     let window = translate_window();
-    let position_type = match get("translate_window_position") {
-        Some(v) => v.as_str().unwrap().to_string(),
-        None => "mouse".to_string(),
-    };
-    if position_type == "mouse" {
+    if AppConfig::load().translate_window_position == "mouse" {
         window.center().unwrap();
     }
 
+    broadcast("input", json!({}));
     window.emit("new_text", "[INPUT_TRANSLATE]").unwrap();
 }
 
@@ -300,6 +435,7 @@ pub fn text_translate(text: String) {
     let state: tauri::State<StringWrapper> = app_handle.state();
     state.0.lock().unwrap().replace_range(.., &text);
     let window = translate_window();
+    broadcast("new_text", json!({ "text": text }));
     window.emit("new_text", text).unwrap();
 }
 
@@ -312,29 +448,21 @@ pub fn image_translate() {
         .unwrap()
         .replace_range(.., "[IMAGE_TRANSLATE]");
     let window = translate_window();
+    broadcast("new_image", json!({ "translate": true }));
     window.emit("new_text", "[IMAGE_TRANSLATE]").unwrap();
 }
 
 pub fn recognize_window() {
     let (window, exists) = build_window("recognize", "Recognize");
+    let options = take_window_options(&window);
+    broadcast("new_image", json!({ "translate": false }));
     if exists {
         window.emit("new_image", "").unwrap();
         return;
     }
-    let width = match get("recognize_window_width") {
-        Some(v) => v.as_i64().unwrap(),
-        None => {
-            set("recognize_window_width", 800);
-            800
-        }
-    };
-    let height = match get("recognize_window_height") {
-        Some(v) => v.as_i64().unwrap(),
-        None => {
-            set("recognize_window_height", 400);
-            400
-        }
-    };
+    let config = AppConfig::load();
+    let width = config.recognize_window_width;
+    let height = config.recognize_window_height;
     let monitor = window.current_monitor().unwrap().unwrap();
     let dpi = monitor.scale_factor();
     window
@@ -343,7 +471,23 @@ pub fn recognize_window() {
             (height as f64) * dpi,
         ))
         .unwrap();
-    window.center().unwrap();
+    match options.position {
+        Some(WindowPosition::Mouse) => {
+            use mouse_position::mouse_position::Mouse;
+            if let Mouse::Position { x, y } = Mouse::get_mouse_position() {
+                window
+                    .set_position(tauri::PhysicalPosition::new(x, y))
+                    .unwrap();
+            }
+        }
+        Some(WindowPosition::Fixed(x, y)) => window
+            .set_position(tauri::PhysicalPosition::new(
+                (x as f64) * dpi,
+                (y as f64) * dpi,
+            ))
+            .unwrap(),
+        _ => window.center().unwrap(),
+    }
     window.emit("new_image", "").unwrap();
 }
 
@@ -395,6 +539,7 @@ pub fn ocr_recognize() {
         let window = screenshot_window();
         let window_ = window.clone();
         window.listen("success", move |event| {
+            broadcast("screenshot", json!({}));
             recognize_window();
             window_.unlisten(event.id())
         });
@@ -429,6 +574,7 @@ pub fn ocr_translate() {
         let window = screenshot_window();
         let window_ = window.clone();
         window.listen("success", move |event| {
+            broadcast("screenshot", json!({}));
             image_translate();
             window_.unlisten(event.id())
         });
@@ -444,3 +590,59 @@ pub fn updater_window() {
     window.set_size(tauri::LogicalSize::new(600, 400)).unwrap();
     window.center().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn from_query_reads_the_options() {
+        let options = WindowOptions::from_query(&query(&[
+            ("source", "en"),
+            ("target", "zh_cn"),
+            ("service", "deepl, google@abc ,"),
+            ("position", "100, -20"),
+            ("unknown", "ignored"),
+        ]))
+        .unwrap();
+        assert_eq!(options.source.as_deref(), Some("en"));
+        assert_eq!(options.target.as_deref(), Some("zh_cn"));
+        assert_eq!(
+            options.services,
+            Some(vec!["deepl".to_string(), "google@abc".to_string()])
+        );
+        assert!(matches!(
+            options.position,
+            Some(WindowPosition::Fixed(100, -20))
+        ));
+        assert!(options.lang.is_none());
+    }
+
+    #[test]
+    fn from_query_reads_named_positions() {
+        let options = WindowOptions::from_query(&query(&[("position", "mouse")])).unwrap();
+        assert!(matches!(options.position, Some(WindowPosition::Mouse)));
+        let options = WindowOptions::from_query(&query(&[("position", "center")])).unwrap();
+        assert!(matches!(options.position, Some(WindowPosition::Center)));
+    }
+
+    #[test]
+    fn from_query_rejects_invalid_values() {
+        for pairs in [
+            [("source", "en-US")],
+            [("lang", "")],
+            [("service", " , ")],
+            [("position", "top")],
+            [("position", "1,x")],
+        ] {
+            assert!(WindowOptions::from_query(&query(&pairs)).is_err());
+        }
+    }
+}
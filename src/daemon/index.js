@@ -0,0 +1,30 @@
+import { emit, listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/tauri';
+import { error as logError } from 'tauri-plugin-log-api';
+
+import { initStore } from '../utils/store';
+import { initEnv } from '../utils/env';
+import { apiRecognize } from './recognize';
+import { apiTranslate } from './translate';
+
+// Run api jobs sent by the http server and send the result back
+function handle(event, handler) {
+    return listen(event, async (e) => {
+        const { id, ...payload } = e.payload;
+        let result;
+        try {
+            result = await handler(payload);
+        } catch (err) {
+            logError(`[${event}]happened error: ` + err);
+            result = { error: err.toString() };
+        }
+        await invoke('api_result', { id, result });
+    });
+}
+
+initStore().then(async () => {
+    await initEnv();
+    await Promise.all([handle('api_translate', apiTranslate), handle('api_recognize', apiRecognize)]);
+    // Let the backend know api jobs can be sent now
+    await emit('daemon_ready');
+});
@@ -0,0 +1,51 @@
+import { BaseDirectory, readTextFile } from '@tauri-apps/api/fs';
+import { emit } from '@tauri-apps/api/event';
+import { info } from 'tauri-plugin-log-api';
+
+import * as builtinServices from '../services/recognize';
+import { getServiceName, whetherPluginService } from '../utils/service_instance';
+import { invoke_plugin } from '../utils/invoke_plugin';
+import { store } from '../utils/store';
+import { apiTranslate } from './translate';
+
+async function recognizeByPlugin(serviceInstanceKey, base64, lang) {
+    const pluginName = getServiceName(serviceInstanceKey);
+    const infoStr = await readTextFile(`plugins/recognize/${pluginName}/info.json`, {
+        dir: BaseDirectory.AppConfig,
+    });
+    const pluginInfo = JSON.parse(infoStr);
+    if (!(lang in pluginInfo.language)) {
+        throw 'Language not supported';
+    }
+    const config = (await store.get(serviceInstanceKey)) ?? {};
+    const [func, utils] = await invoke_plugin('recognize', pluginName);
+    return await func(base64, pluginInfo.language[lang], { config, utils });
+}
+
+async function recognizeByBuiltin(serviceInstanceKey, base64, lang, path) {
+    const serviceName = getServiceName(serviceInstanceKey);
+    if (!(serviceName in builtinServices)) {
+        throw `Service not found: ${serviceName}`;
+    }
+    const LanguageEnum = builtinServices[serviceName].Language;
+    if (!(lang in LanguageEnum)) {
+        throw 'Language not supported';
+    }
+    const config = (await store.get(serviceInstanceKey)) ?? {};
+    return await builtinServices[serviceName].recognize(base64, LanguageEnum[lang], { config, path });
+}
+
+// Recognize an uploaded image, and translate the text if asked to
+export async function apiRecognize({ base64, path, service, lang, translate }) {
+    await store.load();
+    const text = whetherPluginService(service)
+        ? await recognizeByPlugin(service, base64, lang)
+        : await recognizeByBuiltin(service, base64, lang, path);
+    info(`[${service}]resolve:` + text);
+    const result = { service, lang, text: text.trim() };
+    emit('recognize_result', result);
+    if (translate && result.text !== '') {
+        result.translation = await apiTranslate({ ...translate, text: result.text });
+    }
+    return result;
+}
@@ -0,0 +1,82 @@
+import { BaseDirectory, readTextFile } from '@tauri-apps/api/fs';
+import { emit } from '@tauri-apps/api/event';
+import { info, error as logError } from 'tauri-plugin-log-api';
+
+import * as builtinServices from '../services/translate';
+import { getServiceName, whetherPluginService } from '../utils/service_instance';
+import { invoke_plugin } from '../utils/invoke_plugin';
+import detect from '../utils/lang_detect';
+import { store } from '../utils/store';
+
+async function translateByPlugin(serviceInstanceKey, text, source, target, detectLanguage) {
+    const pluginName = getServiceName(serviceInstanceKey);
+    const infoStr = await readTextFile(`plugins/translate/${pluginName}/info.json`, {
+        dir: BaseDirectory.AppConfig,
+    });
+    const pluginInfo = JSON.parse(infoStr);
+    if (!(source in pluginInfo.language && target in pluginInfo.language)) {
+        throw 'Language not supported';
+    }
+    const config = (await store.get(serviceInstanceKey)) ?? {};
+    config['enable'] = 'true';
+    const [func, utils] = await invoke_plugin('translate', pluginName);
+    return await func(text, pluginInfo.language[source], pluginInfo.language[target], {
+        config,
+        detect: detectLanguage,
+        setResult: () => {},
+        utils,
+    });
+}
+
+async function translateByBuiltin(serviceInstanceKey, text, source, target, detectLanguage) {
+    const serviceName = getServiceName(serviceInstanceKey);
+    if (!(serviceName in builtinServices)) {
+        throw `Service not found: ${serviceName}`;
+    }
+    const LanguageEnum = builtinServices[serviceName].Language;
+    if (!(source in LanguageEnum && target in LanguageEnum)) {
+        throw 'Language not supported';
+    }
+    const config = (await store.get(serviceInstanceKey)) ?? {};
+    return await builtinServices[serviceName].translate(text, LanguageEnum[source], LanguageEnum[target], {
+        config,
+        detect: detectLanguage,
+        setResult: () => {},
+    });
+}
+
+// Translate text with every service in the list, without opening the translate window
+export async function apiTranslate({ text, source, target, services }) {
+    await store.load();
+    const sourceText = text.trim();
+    const translateSecondLanguage = (await store.get('translate_second_language')) ?? 'en';
+    const detectLanguage = await detect(sourceText);
+    let newTarget = target;
+    if (source === 'auto' && target === detectLanguage) {
+        newTarget = translateSecondLanguage;
+    }
+
+    const results = await Promise.all(
+        services.map(async (serviceInstanceKey) => {
+            const translate = whetherPluginService(serviceInstanceKey) ? translateByPlugin : translateByBuiltin;
+            try {
+                const v = await translate(serviceInstanceKey, sourceText, source, newTarget, detectLanguage);
+                info(`[${serviceInstanceKey}]resolve:` + v);
+                const result = typeof v === 'string' ? v.trim() : v;
+                emit('translate_result', {
+                    text: sourceText,
+                    source: detectLanguage,
+                    target: newTarget,
+                    service: serviceInstanceKey,
+                    result,
+                });
+                return { service: serviceInstanceKey, result, error: null };
+            } catch (e) {
+                logError(`[${serviceInstanceKey}]happened error: ` + e);
+                return { service: serviceInstanceKey, result: null, error: e.toString() };
+            }
+        })
+    );
+
+    return { text: sourceText, source, target: newTarget, detect: detectLanguage, results };
+}
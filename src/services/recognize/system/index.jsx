@@ -3,7 +3,9 @@ import { osType } from '../../../utils/env';
 import { invoke } from '@tauri-apps/api';
 import { Language } from './info';
 
-export async function recognize(_, lang) {
+export async function recognize(_, lang, options = {}) {
+    // Recognize the screenshot unless another image file is given
+    const { path = null } = options;
     const linuxLangMap = {
         auto: 'auto',
         zh_cn: 'chi_sim',
@@ -82,7 +84,7 @@ export async function recognize(_, lang) {
     let result = '';
     switch (osType) {
         case 'Linux':
-            result = await invoke('system_ocr', { lang: linuxLangMap[lang] });
+            result = await invoke('system_ocr', { lang: linuxLangMap[lang], path });
             if (lang === Language.auto && (await detect(result)) === Language.zh_cn) {
                 result = result.replaceAll(' ', '');
             } else {
@@ -92,10 +94,10 @@ export async function recognize(_, lang) {
             }
             return result.trim();
         case 'Darwin':
-            result = await invoke('system_ocr', { lang: macOSLangMap[lang] });
+            result = await invoke('system_ocr', { lang: macOSLangMap[lang], path });
             return result.trim();
         case 'Windows_NT':
-            result = await invoke('system_ocr', { lang: windowsLangMap[lang] });
+            result = await invoke('system_ocr', { lang: windowsLangMap[lang], path });
             if (lang === Language.auto && (await detect(result)) === Language.zh_cn) {
                 result = result.replaceAll(' ', '');
             } else {
@@ -3,14 +3,50 @@ import { appConfigDir, join } from '@tauri-apps/api/path';
 import { watch } from 'tauri-plugin-fs-watch-api';
 import { invoke } from '@tauri-apps/api';
 
-export let store = new Store();
+// Values forced with `pot --set`, `POT_*` environment variables or the system policy
+let overrides = {};
+// Keys the system policy doesn't allow to change
+let locked = [];
+
+export function isLocked(key) {
+    return locked.includes(key);
+}
+
+// Secrets are kept in the system keyring, config.json only has references to them
+class SecretStore extends Store {
+    async get(key) {
+        if (key in overrides) {
+            return overrides[key];
+        }
+        const value = await super.get(key);
+        if (!JSON.stringify(value ?? null).includes('secret://')) {
+            return value;
+        }
+        return await invoke('open_config', { value });
+    }
+
+    async set(key, value) {
+        if (isLocked(key)) {
+            return;
+        }
+        if (typeof value === 'string' || (typeof value === 'object' && value !== null)) {
+            value = await invoke('seal_config', { key, value });
+        }
+        return await super.set(key, value);
+    }
+}
+
+export let store = new SecretStore();
 
 export async function initStore() {
     const appConfigDirPath = await appConfigDir();
     const appConfigPath = await join(appConfigDirPath, 'config.json');
-    store = new Store(appConfigPath);
+    store = new SecretStore(appConfigPath);
+    overrides = await invoke('get_config_overrides');
+    const policy = await invoke('get_policy');
+    locked = Object.keys(policy.locked);
+    // The backend reloads its own copy and applies the changes
     const _ = await watch(appConfigPath, async () => {
         await store.load();
-        await invoke('reload_store');
     });
 }
@@ -0,0 +1,95 @@
+import { Modal, ModalContent, ModalHeader, ModalBody, ModalFooter, Button } from '@nextui-org/react';
+import { CheckboxGroup, Checkbox } from '@nextui-org/react';
+import toast, { Toaster } from 'react-hot-toast';
+import { useTranslation } from 'react-i18next';
+import { save } from '@tauri-apps/api/dialog';
+import { invoke } from '@tauri-apps/api';
+import React, { useState } from 'react';
+
+import { useToastStyle } from '../../../../../hooks';
+
+export const configSections = ['hotkeys', 'services', 'appearance', 'plugins'];
+
+export default function ExportModal(props) {
+    const { isOpen, onOpenChange } = props;
+    const [sections, setSections] = useState(configSections);
+    const [exporting, setExporting] = useState(false);
+
+    const { t } = useTranslation();
+    const toastStyle = useToastStyle();
+
+    const onExport = async (onClose) => {
+        const selected = await save({
+            defaultPath: 'pot-config.json',
+            filters: [
+                {
+                    name: 'JSON',
+                    extensions: ['json'],
+                },
+            ],
+        });
+        if (selected === null) return;
+        setExporting(true);
+        invoke('export_config', { path: selected, sections }).then(
+            () => {
+                setExporting(false);
+                toast.success(t('config.backup.export_success'), { style: toastStyle });
+                onClose();
+            },
+            (e) => {
+                setExporting(false);
+                toast.error(e.toString(), { style: toastStyle });
+            }
+        );
+    };
+
+    return (
+        <Modal
+            isOpen={isOpen}
+            onOpenChange={onOpenChange}
+        >
+            <Toaster />
+            <ModalContent>
+                {(onClose) => (
+                    <>
+                        <ModalHeader>{t('config.backup.export_config')}</ModalHeader>
+                        <ModalBody>
+                            <CheckboxGroup
+                                label={t('config.backup.sections')}
+                                value={sections}
+                                onValueChange={setSections}
+                            >
+                                {configSections.map((section) => (
+                                    <Checkbox
+                                        key={section}
+                                        value={section}
+                                    >
+                                        {t(`config.backup.section.${section}`)}
+                                    </Checkbox>
+                                ))}
+                            </CheckboxGroup>
+                            <p className='text-sm text-default-500'>{t('config.backup.redacted')}</p>
+                        </ModalBody>
+                        <ModalFooter>
+                            <Button
+                                color='danger'
+                                variant='light'
+                                onPress={onClose}
+                            >
+                                {t('common.cancel')}
+                            </Button>
+                            <Button
+                                color='primary'
+                                isDisabled={sections.length === 0}
+                                isLoading={exporting}
+                                onPress={() => onExport(onClose)}
+                            >
+                                {t('config.backup.export')}
+                            </Button>
+                        </ModalFooter>
+                    </>
+                )}
+            </ModalContent>
+        </Modal>
+    );
+}
@@ -0,0 +1,176 @@
+import { Modal, ModalContent, ModalHeader, ModalBody, ModalFooter, Button, Skeleton } from '@nextui-org/react';
+import { CheckboxGroup, Checkbox, Chip } from '@nextui-org/react';
+import React, { useEffect, useState } from 'react';
+import toast, { Toaster } from 'react-hot-toast';
+import { useTranslation } from 'react-i18next';
+import { invoke } from '@tauri-apps/api';
+
+import { useToastStyle } from '../../../../../hooks';
+import { configSections } from '../ExportModal';
+
+// Shows what importing a shared config changes before anything is saved
+export default function ImportModal(props) {
+    const { isOpen, onOpenChange, path } = props;
+    const [fileSections, setFileSections] = useState([]);
+    const [sections, setSections] = useState([]);
+    const [changes, setChanges] = useState([]);
+    // Conflicting keys that take the imported value
+    const [overwrite, setOverwrite] = useState([]);
+    const [loading, setLoading] = useState(false);
+    const [importing, setImporting] = useState(false);
+
+    const { t } = useTranslation();
+    const toastStyle = useToastStyle();
+
+    useEffect(() => {
+        if (isOpen && path) {
+            setLoading(true);
+            invoke('preview_import_config', { path }).then(
+                (v) => {
+                    const available = configSections.filter((section) => v.sections.includes(section));
+                    setFileSections(available);
+                    setSections(available);
+                    setChanges(v.changes);
+                    setOverwrite(v.changes.filter((change) => change.conflict).map((change) => change.key));
+                    setLoading(false);
+                },
+                (e) => {
+                    toast.error(e.toString(), { style: toastStyle });
+                    setChanges([]);
+                    setFileSections([]);
+                    setLoading(false);
+                }
+            );
+        }
+    }, [isOpen, path]);
+
+    const onImport = (onClose) => {
+        setImporting(true);
+        const keep = changes
+            .filter((change) => change.conflict && !overwrite.includes(change.key))
+            .map((change) => change.key);
+        invoke('import_config', { path, sections, keep }).then(
+            () => {
+                setImporting(false);
+                toast.success(t('config.backup.import_success'), { style: toastStyle });
+                onClose();
+            },
+            (e) => {
+                setImporting(false);
+                toast.error(e.toString(), { style: toastStyle });
+            }
+        );
+    };
+
+    const format = (value) => (value === undefined || value === null ? '' : JSON.stringify(value));
+    const selected = changes.filter((change) => sections.includes(change.section));
+    const conflicts = selected.filter((change) => change.conflict);
+    const additions = selected.filter((change) => !change.conflict);
+
+    return (
+        <Modal
+            isOpen={isOpen}
+            onOpenChange={onOpenChange}
+            scrollBehavior='inside'
+        >
+            <Toaster />
+            <ModalContent className='max-h-[80vh]'>
+                {(onClose) => (
+                    <>
+                        <ModalHeader>{t('config.backup.import_config')}</ModalHeader>
+                        <ModalBody>
+                            {loading ? (
+                                <div className='space-y-3'>
+                                    <Skeleton className='w-4/5 rounded-lg'>
+                                        <div className='h-3 w-4/5 rounded-lg bg-default-200'></div>
+                                    </Skeleton>
+                                    <Skeleton className='w-3/5 rounded-lg'>
+                                        <div className='h-3 w-3/5 rounded-lg bg-default-200'></div>
+                                    </Skeleton>
+                                </div>
+                            ) : (
+                                <>
+                                    <CheckboxGroup
+                                        label={t('config.backup.sections')}
+                                        orientation='horizontal'
+                                        value={sections}
+                                        onValueChange={setSections}
+                                    >
+                                        {fileSections.map((section) => (
+                                            <Checkbox
+                                                key={section}
+                                                value={section}
+                                            >
+                                                {t(`config.backup.section.${section}`)}
+                                            </Checkbox>
+                                        ))}
+                                    </CheckboxGroup>
+                                    {selected.length === 0 && <h2>{t('config.backup.no_changes')}</h2>}
+                                    {additions.length > 0 && (
+                                        <div>
+                                            <h3 className='mb-[8px]'>{t('config.backup.changes')}</h3>
+                                            {additions.map((change) => (
+                                                <div
+                                                    key={`${change.section}.${change.key}`}
+                                                    className='flex justify-between text-sm mb-[4px]'
+                                                >
+                                                    <code>{change.key}</code>
+                                                    <Chip
+                                                        size='sm'
+                                                        variant='flat'
+                                                    >
+                                                        {t(
+                                                            change.local === null
+                                                                ? 'config.backup.added'
+                                                                : 'config.backup.merged'
+                                                        )}
+                                                    </Chip>
+                                                </div>
+                                            ))}
+                                        </div>
+                                    )}
+                                    {conflicts.length > 0 && (
+                                        <CheckboxGroup
+                                            label={t('config.backup.conflicts')}
+                                            value={overwrite}
+                                            onValueChange={setOverwrite}
+                                        >
+                                            {conflicts.map((change) => (
+                                                <Checkbox
+                                                    key={change.key}
+                                                    value={change.key}
+                                                >
+                                                    <code>{change.key}</code>
+                                                    <p className='text-xs text-default-500 break-all'>
+                                                        {format(change.local)} → {format(change.imported)}
+                                                    </p>
+                                                </Checkbox>
+                                            ))}
+                                        </CheckboxGroup>
+                                    )}
+                                </>
+                            )}
+                        </ModalBody>
+                        <ModalFooter>
+                            <Button
+                                color='danger'
+                                variant='light'
+                                onPress={onClose}
+                            >
+                                {t('common.cancel')}
+                            </Button>
+                            <Button
+                                color='primary'
+                                isDisabled={loading || selected.length === 0}
+                                isLoading={importing}
+                                onPress={() => onImport(onClose)}
+                            >
+                                {t('config.backup.import')}
+                            </Button>
+                        </ModalFooter>
+                    </>
+                )}
+            </ModalContent>
+        </Modal>
+    );
+}
@@ -10,17 +10,25 @@ import { Dropdown } from '@nextui-org/react';
 import { warn } from 'tauri-plugin-log-api';
 import { Button } from '@nextui-org/react';
 import { Input } from '@nextui-org/react';
+import { Switch } from '@nextui-org/react';
 import { Card } from '@nextui-org/react';
 import { Avatar, Tooltip } from '@nextui-org/react';
 import React, { useEffect, useState } from 'react';
+import { open } from '@tauri-apps/api/dialog';
+import { invoke } from '@tauri-apps/api';
 
 import { useConfig, useToastStyle } from '../../../../hooks';
 import { osType } from '../../../../utils/env';
 import * as webdav from './utils/webdav';
 import WebDavModal from './WebDavModal';
 import AliyunModal from './AliyunModal';
+import S3Modal from './S3Modal';
+import ExportModal from './ExportModal';
+import ImportModal from './ImportModal';
 import * as local from './utils/local';
+import { closeHistory } from './utils/history';
 import * as aliyun from './utils/aliyun';
+import * as s3 from './utils/s3';
 
 let refreshTimer = null;
 
@@ -33,6 +41,25 @@ export default function Backup() {
     const [aliyunUserInfo, setAliyunUserInfo] = useState(null);
     const [aliyunAccessToken, setAliyunAccessToken] = useConfig('aliyun_access_token', '');
     // const [aliyunRefreshToken, setAliyunRefreshToken] = useConfig('aliyun_refresh_token', '');
+    const [backupPassword, setBackupPassword] = useConfig('backup_password', '');
+    const [backupMergeHistory, setBackupMergeHistory] = useConfig('backup_merge_history', false);
+    const [backupSchedule, setBackupSchedule] = useConfig('backup_schedule', 'disable');
+    const [backupKeepLast, setBackupKeepLast] = useConfig('backup_keep_last', 7);
+    const [backupKeepDaily, setBackupKeepDaily] = useConfig('backup_keep_daily', 7);
+    const [backupKeepWeekly, setBackupKeepWeekly] = useConfig('backup_keep_weekly', 4);
+    const [backupLocalDir, setBackupLocalDir] = useConfig('backup_local_dir', '');
+    const [s3Endpoint, setS3Endpoint] = useConfig('s3_endpoint', '');
+    const [s3Region, setS3Region] = useConfig('s3_region', '');
+    const [s3Bucket, setS3Bucket] = useConfig('s3_bucket', '');
+    const [s3AccessKey, setS3AccessKey] = useConfig('s3_access_key', '');
+    const [s3SecretKey, setS3SecretKey] = useConfig('s3_secret_key', '');
+    const s3Options = {
+        endpoint: s3Endpoint,
+        region: s3Region,
+        bucket: s3Bucket,
+        accessKey: s3AccessKey,
+        secretKey: s3SecretKey,
+    };
     const {
         isOpen: isWebDavListOpen,
         onOpen: onWebDavListOpen,
@@ -43,6 +70,22 @@ export default function Backup() {
         onOpen: onAliyunListOpen,
         onOpenChange: onAliyunListOpenChange,
     } = useDisclosure();
+    const {
+        isOpen: isS3ListOpen,
+        onOpen: onS3ListOpen,
+        onOpenChange: onS3ListOpenChange,
+    } = useDisclosure();
+    const {
+        isOpen: isExportOpen,
+        onOpen: onExportOpen,
+        onOpenChange: onExportOpenChange,
+    } = useDisclosure();
+    const {
+        isOpen: isImportOpen,
+        onOpen: onImportOpen,
+        onOpenChange: onImportOpenChange,
+    } = useDisclosure();
+    const [importPath, setImportPath] = useState(null);
     const [uploading, setUploading] = useState(false);
     const toastStyle = useToastStyle();
     const { t } = useTranslation();
@@ -70,6 +113,9 @@ export default function Backup() {
                     result = aliyun.backup(aliyunAccessToken, fileName + '.zip');
                 }
                 break;
+            case 's3':
+                result = s3.backup(s3Options, fileName + '.zip');
+                break;
             default:
                 warn('Unknown backup type');
                 return;
@@ -108,12 +154,44 @@ export default function Backup() {
                     onAliyunListOpen();
                 }
 
+                break;
+            case 's3':
+                onS3ListOpen();
                 break;
             default:
                 warn('Unknown backup type');
         }
     };
 
+    const onImport = async () => {
+        const selected = await open({
+            multiple: false,
+            directory: false,
+            filters: [
+                {
+                    name: '*.json',
+                    extensions: ['json'],
+                },
+            ],
+        });
+        if (selected !== null) {
+            setImportPath(selected);
+            onImportOpen();
+        }
+    };
+
+    const onUndoRestore = async () => {
+        await closeHistory();
+        invoke('undo_restore').then(
+            () => {
+                toast.success(t('config.backup.undo_restore_success'), { style: toastStyle });
+            },
+            (e) => {
+                toast.error(e.toString(), { style: toastStyle });
+            }
+        );
+    };
+
     const pollingStatus = async (sid) => {
         refreshTimer = setInterval(async () => {
             try {
@@ -197,6 +275,7 @@ export default function Backup() {
                             >
                                 <DropdownItem key='webdav'>{t('config.backup.webdav')}</DropdownItem>
                                 <DropdownItem key='aliyun'>{t('config.backup.aliyun')}</DropdownItem>
+                                <DropdownItem key='s3'>{t('config.backup.s3')}</DropdownItem>
                                 <DropdownItem key='local'>{t('config.backup.local')}</DropdownItem>
                             </DropdownMenu>
                         </Dropdown>
@@ -247,6 +326,76 @@ export default function Backup() {
                         )}
                     </div>
                 </div>
+                <div className={backupType !== 's3' ? 'hidden' : ''}>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.s3_endpoint')}</h3>
+                        {s3Endpoint !== null && (
+                            <Input
+                                variant='bordered'
+                                value={s3Endpoint}
+                                placeholder='https://s3.example.com'
+                                onValueChange={(v) => {
+                                    setS3Endpoint(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.s3_region')}</h3>
+                        {s3Region !== null && (
+                            <Input
+                                variant='bordered'
+                                value={s3Region}
+                                placeholder='us-east-1'
+                                onValueChange={(v) => {
+                                    setS3Region(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.s3_bucket')}</h3>
+                        {s3Bucket !== null && (
+                            <Input
+                                variant='bordered'
+                                value={s3Bucket}
+                                onValueChange={(v) => {
+                                    setS3Bucket(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.s3_access_key')}</h3>
+                        {s3AccessKey !== null && (
+                            <Input
+                                variant='bordered'
+                                value={s3AccessKey}
+                                onValueChange={(v) => {
+                                    setS3AccessKey(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.s3_secret_key')}</h3>
+                        {s3SecretKey !== null && (
+                            <Input
+                                type='password'
+                                variant='bordered'
+                                value={s3SecretKey}
+                                onValueChange={(v) => {
+                                    setS3SecretKey(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                </div>
                 <div className={`flex justify-center ${backupType !== 'aliyun' ? 'hidden' : ''}`}>
                     <img
                         src={aliyunQrCodeUrl}
@@ -281,6 +430,135 @@ export default function Backup() {
                         </>
                     )}
                 </div>
+                <div className='config-item'>
+                    <h3 className='my-auto'>{t('config.backup.backup_password')}</h3>
+                    {backupPassword !== null && (
+                        <Input
+                            type='password'
+                            variant='bordered'
+                            value={backupPassword}
+                            placeholder={t('config.backup.backup_password_placeholder')}
+                            onValueChange={(v) => {
+                                setBackupPassword(v);
+                            }}
+                            className='max-w-[300px]'
+                        />
+                    )}
+                </div>
+                <div className='config-item'>
+                    <h3 className='my-auto'>{t('config.backup.merge_history')}</h3>
+                    {backupMergeHistory !== null && (
+                        <Switch
+                            isSelected={backupMergeHistory}
+                            onValueChange={(v) => {
+                                setBackupMergeHistory(v);
+                            }}
+                        />
+                    )}
+                </div>
+                <div className='config-item'>
+                    <h3 className='my-auto'>{t('config.backup.schedule')}</h3>
+                    {backupSchedule !== null && (
+                        <Dropdown>
+                            <DropdownTrigger>
+                                <Button variant='bordered'>{t(`config.backup.schedule_${backupSchedule}`)}</Button>
+                            </DropdownTrigger>
+                            <DropdownMenu
+                                aria-label='backup schedule'
+                                onAction={(key) => {
+                                    setBackupSchedule(key);
+                                }}
+                            >
+                                <DropdownItem key='disable'>{t('config.backup.schedule_disable')}</DropdownItem>
+                                <DropdownItem key='daily'>{t('config.backup.schedule_daily')}</DropdownItem>
+                                <DropdownItem key='weekly'>{t('config.backup.schedule_weekly')}</DropdownItem>
+                            </DropdownMenu>
+                        </Dropdown>
+                    )}
+                </div>
+                <div className={backupSchedule === 'disable' ? 'hidden' : ''}>
+                    <div className={`config-item ${backupType !== 'local' ? 'hidden' : ''}`}>
+                        <h3 className='my-auto'>{t('config.backup.local_dir')}</h3>
+                        {backupLocalDir !== null && (
+                            <Input
+                                variant='bordered'
+                                value={backupLocalDir}
+                                onValueChange={(v) => {
+                                    setBackupLocalDir(v);
+                                }}
+                                endContent={
+                                    <Button
+                                        size='sm'
+                                        variant='flat'
+                                        onPress={async () => {
+                                            const selected = await open({ directory: true });
+                                            if (selected !== null) {
+                                                setBackupLocalDir(selected);
+                                            }
+                                        }}
+                                    >
+                                        {t('config.backup.choose')}
+                                    </Button>
+                                }
+                                className='max-w-[300px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.keep_last')}</h3>
+                        {backupKeepLast !== null && (
+                            <Input
+                                type='number'
+                                variant='bordered'
+                                value={backupKeepLast}
+                                onValueChange={(v) => {
+                                    if (v === '' || parseInt(v) < 0) {
+                                        setBackupKeepLast(0);
+                                    } else {
+                                        setBackupKeepLast(Math.min(parseInt(v), 1000));
+                                    }
+                                }}
+                                className='max-w-[100px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.keep_daily')}</h3>
+                        {backupKeepDaily !== null && (
+                            <Input
+                                type='number'
+                                variant='bordered'
+                                value={backupKeepDaily}
+                                onValueChange={(v) => {
+                                    if (v === '' || parseInt(v) < 0) {
+                                        setBackupKeepDaily(0);
+                                    } else {
+                                        setBackupKeepDaily(Math.min(parseInt(v), 1000));
+                                    }
+                                }}
+                                className='max-w-[100px]'
+                            />
+                        )}
+                    </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.backup.keep_weekly')}</h3>
+                        {backupKeepWeekly !== null && (
+                            <Input
+                                type='number'
+                                variant='bordered'
+                                value={backupKeepWeekly}
+                                onValueChange={(v) => {
+                                    if (v === '' || parseInt(v) < 0) {
+                                        setBackupKeepWeekly(0);
+                                    } else {
+                                        setBackupKeepWeekly(Math.min(parseInt(v), 1000));
+                                    }
+                                }}
+                                className='max-w-[100px]'
+                            />
+                        )}
+                    </div>
+                </div>
                 <div className='flex justify-around'>
                     <Button
                         color='success'
@@ -298,6 +576,26 @@ export default function Backup() {
                         {t('config.backup.restore')}
                     </Button>
                 </div>
+                <div className='flex justify-around mt-[8px]'>
+                    <Button
+                        variant='flat'
+                        onPress={onExportOpen}
+                    >
+                        {t('config.backup.export_config')}
+                    </Button>
+                    <Button
+                        variant='flat'
+                        onPress={onImport}
+                    >
+                        {t('config.backup.import_config')}
+                    </Button>
+                    <Button
+                        variant='flat'
+                        onPress={onUndoRestore}
+                    >
+                        {t('config.backup.undo_restore')}
+                    </Button>
+                </div>
             </CardBody>
             <WebDavModal
                 isOpen={isWebDavListOpen}
@@ -312,6 +610,20 @@ export default function Backup() {
                 accessToken={aliyunAccessToken}
                 // refreshToken={aliyunRefreshToken}
             />
+            <S3Modal
+                isOpen={isS3ListOpen}
+                onOpenChange={onS3ListOpenChange}
+                options={s3Options}
+            />
+            <ExportModal
+                isOpen={isExportOpen}
+                onOpenChange={onExportOpenChange}
+            />
+            <ImportModal
+                isOpen={isImportOpen}
+                onOpenChange={onImportOpenChange}
+                path={importPath}
+            />
         </Card>
     );
 }
@@ -0,0 +1,8 @@
+import Database from 'tauri-plugin-sql-api';
+
+// A restore swaps history.db, the open pool would keep using the replaced file
+// and on Windows the file can't be moved at all. The next Database.load reopens it
+export async function closeHistory() {
+    const db = await Database.load('sqlite:history.db');
+    await db.close();
+}
@@ -1,6 +1,8 @@
 import { save, open } from '@tauri-apps/api/dialog';
 import { invoke } from '@tauri-apps/api';
 
+import { closeHistory } from './history';
+
 export async function backup() {
     const selected = await save({
         filters: [
@@ -33,6 +35,7 @@ export async function get() {
     });
 
     if (selected !== null && selected.endsWith('zip')) {
+        await closeHistory();
         return await invoke('local', {
             operate: 'get',
             path: selected,
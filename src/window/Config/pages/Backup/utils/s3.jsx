@@ -0,0 +1,35 @@
+import { invoke } from '@tauri-apps/api';
+
+import { closeHistory } from './history';
+
+export async function backup(options, name) {
+    return await invoke('s3', {
+        operate: 'put',
+        ...options,
+        name,
+    });
+}
+
+export async function list(options) {
+    return await invoke('s3', {
+        operate: 'list',
+        ...options,
+    });
+}
+
+export async function get(options, name) {
+    await closeHistory();
+    return await invoke('s3', {
+        operate: 'get',
+        ...options,
+        name,
+    });
+}
+
+export async function remove(options, name) {
+    return await invoke('s3', {
+        operate: 'delete',
+        ...options,
+        name,
+    });
+}
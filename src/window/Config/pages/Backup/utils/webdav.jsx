@@ -1,5 +1,7 @@
 import { invoke } from '@tauri-apps/api';
 
+import { closeHistory } from './history';
+
 export async function backup(url, username, password, name) {
     return await invoke('webdav', {
         operate: 'put',
@@ -11,22 +13,16 @@ export async function backup(url, username, password, name) {
 }
 
 export async function list(url, username, password) {
-    const backup_list_text = await invoke('webdav', {
+    return await invoke('webdav', {
         operate: 'list',
         url,
         username,
         password,
     });
-    let backup_list = JSON.parse(backup_list_text);
-    backup_list = backup_list.filter((item) => {
-        return item.hasOwnProperty('File');
-    });
-    return backup_list.map((file) => {
-        return file.File.href.split('/').slice(-1)[0];
-    });
 }
 
 export async function get(url, username, password, name) {
+    await closeHistory();
     const _ = await invoke('webdav', {
         operate: 'get',
         url,
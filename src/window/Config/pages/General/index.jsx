@@ -15,19 +15,25 @@ import { Input } from '@nextui-org/react';
 import { Card } from '@nextui-org/react';
 import { invoke } from '@tauri-apps/api';
 import { useTheme } from 'next-themes';
+import { nanoid } from 'nanoid';
 
 import { useConfig } from '../../../../hooks/useConfig';
 import { LanguageFlag } from '../../../../utils/language';
 import { useToastStyle } from '../../../../hooks';
 import { osType } from '../../../../utils/env';
-
-let timer = null;
+import { isLocked } from '../../../../utils/store';
 
 export default function General() {
     const [autoStart, setAutoStart] = useState(false);
     const [fontList, setFontList] = useState(null);
     const [checkUpdate, setCheckUpdate] = useConfig('check_update', true);
+    const [serverEnable, setServerEnable] = useConfig('server_enable', true);
     const [serverPort, setServerPort] = useConfig('server_port', 60828);
+    const [serverAddress, setServerAddress] = useConfig('server_address', '127.0.0.1');
+    const [serverUnixSocket, setServerUnixSocket] = useConfig('server_unix_socket', '');
+    const [serverUnixSocketMode, setServerUnixSocketMode] = useConfig('server_unix_socket_mode', '600');
+    const [serverAuth, setServerAuth] = useConfig('server_auth', false);
+    const [serverToken, setServerToken] = useConfig('server_token', '');
     const [appLanguage, setAppLanguage] = useConfig('app_language', 'en');
     const [appTheme, setAppTheme] = useConfig('app_theme', 'system');
     const [appFont, setAppFont] = useConfig('app_font', 'default');
@@ -105,12 +111,25 @@ export default function General() {
                         {checkUpdate !== null && (
                             <Switch
                                 isSelected={checkUpdate}
+                                isDisabled={isLocked('check_update')}
                                 onValueChange={(v) => {
                                     setCheckUpdate(v);
                                 }}
                             />
                         )}
                     </div>
+                    <div className='config-item'>
+                        <h3>{t('config.general.server_enable')}</h3>
+                        {serverEnable !== null && (
+                            <Switch
+                                isSelected={serverEnable}
+                                isDisabled={isLocked('server_enable')}
+                                onValueChange={(v) => {
+                                    setServerEnable(v);
+                                }}
+                            />
+                        )}
+                    </div>
                     <div className='config-item'>
                         <h3 className='my-auto'>{t('config.general.server_port')}</h3>
                         {serverPort !== null && (
@@ -119,18 +138,8 @@ export default function General() {
                                 variant='bordered'
                                 value={serverPort}
                                 labelPlacement='outside-left'
+                                isDisabled={isLocked('server_port')}
                                 onValueChange={(v) => {
-                                    if (parseInt(v) !== serverPort) {
-                                        if (timer) {
-                                            clearTimeout(timer);
-                                        }
-                                        timer = setTimeout(() => {
-                                            toast.success(t('config.general.server_port_change'), {
-                                                duration: 3000,
-                                                style: toastStyle,
-                                            });
-                                        }, 1000);
-                                    }
                                     if (v === '') {
                                         setServerPort(0);
                                     } else if (parseInt(v) > 65535) {
@@ -145,6 +154,83 @@ export default function General() {
                             />
                         )}
                     </div>
+                    <div className='config-item'>
+                        <h3 className='my-auto'>{t('config.general.server_address')}</h3>
+                        {serverAddress !== null && (
+                            <Input
+                                variant='bordered'
+                                value={serverAddress}
+                                isDisabled={isLocked('server_address')}
+                                onValueChange={(v) => {
+                                    setServerAddress(v.trim());
+                                }}
+                                className='max-w-[200px]'
+                            />
+                        )}
+                    </div>
+                    {osType === 'Linux' && serverUnixSocket !== null && (
+                        <div className='config-item'>
+                            <h3 className='my-auto'>{t('config.general.server_unix_socket')}</h3>
+                            <Input
+                                variant='bordered'
+                                value={serverUnixSocket}
+                                placeholder='/run/user/1000/pot.sock'
+                                onValueChange={(v) => {
+                                    setServerUnixSocket(v.trim());
+                                }}
+                                className='max-w-[300px]'
+                            />
+                        </div>
+                    )}
+                    {osType === 'Linux' && serverUnixSocket !== '' && serverUnixSocketMode !== null && (
+                        <div className='config-item'>
+                            <h3 className='my-auto'>{t('config.general.server_unix_socket_mode')}</h3>
+                            <Input
+                                variant='bordered'
+                                value={serverUnixSocketMode}
+                                onValueChange={(v) => {
+                                    if (/^[0-7]{0,4}$/.test(v)) {
+                                        setServerUnixSocketMode(v);
+                                    }
+                                }}
+                                className='max-w-[100px]'
+                            />
+                        </div>
+                    )}
+                    <div className='config-item'>
+                        <h3>{t('config.general.server_auth')}</h3>
+                        {serverAuth !== null && (
+                            <Switch
+                                isSelected={serverAuth}
+                                isDisabled={isLocked('server_auth')}
+                                onValueChange={(v) => {
+                                    setServerAuth(v);
+                                }}
+                            />
+                        )}
+                    </div>
+                    {serverAuth && serverToken !== null && (
+                        <div className='config-item'>
+                            <h3 className='my-auto'>{t('config.general.server_token')}</h3>
+                            <div className='flex'>
+                                <Input
+                                    variant='bordered'
+                                    value={serverToken}
+                                    isReadOnly
+                                    className='max-w-[300px]'
+                                />
+                                <Button
+                                    variant='flat'
+                                    className='ml-[8px]'
+                                    onPress={() => {
+                                        setServerToken(nanoid(32));
+                                    }}
+                                >
+                                    {t('config.general.server_token_regenerate')}
+                                </Button>
+                            </div>
+                        </div>
+                    )}
                 </CardBody>
             </Card>
             <Card className='mb-[10px]'>
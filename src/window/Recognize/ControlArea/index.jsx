@@ -1,6 +1,8 @@
 import { Dropdown, DropdownItem, DropdownMenu, DropdownTrigger, Button } from '@nextui-org/react';
 import { atom, useAtom, useSetAtom, useAtomValue } from 'jotai';
 import { fetch, Body } from '@tauri-apps/api/http';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api';
 import { useTranslation } from 'react-i18next';
 import { HiTranslate } from 'react-icons/hi';
 import { GiCycle } from 'react-icons/gi';
@@ -29,6 +31,9 @@ export default function ControlArea(props) {
     const pluginList = useAtomValue(pluginListAtom);
     const [recognizeLanguage] = useConfig('recognize_language', 'auto');
     const [serverPort] = useConfig('server_port', 60828);
+    const [serverAddress] = useConfig('server_address', '127.0.0.1');
+    const [serverAuth] = useConfig('server_auth', false);
+    const [serverToken] = useConfig('server_token', '');
     const setRecognizeFlag = useSetAtom(recognizeFlagAtom);
     const [currentServiceInstanceKey, setCurrentServiceInstanceKey] = useAtom(currentServiceInstanceKeyAtom);
     const [language, setLanguage] = useAtom(languageAtom);
@@ -40,13 +45,30 @@ export default function ControlArea(props) {
         return getDisplayInstanceName(instanceConfig[INSTANCE_NAME_CONFIG_KEY], serviceNameSupplier);
     }
 
-    useEffect(() => {
+    // Service and language passed by the http server take precedence over the config
+    const applyOptions = (options) => {
         if (serviceInstanceList) {
-            setCurrentServiceInstanceKey(serviceInstanceList[0]);
+            const services = options.services ?? [];
+            const serviceInstanceKey = serviceInstanceList.find(
+                (x) => services.includes(x) || services.includes(getServiceName(x))
+            );
+            setCurrentServiceInstanceKey(serviceInstanceKey ?? serviceInstanceList[0]);
         }
         if (recognizeLanguage) {
-            setLanguage(recognizeLanguage);
+            setLanguage(options.lang ?? recognizeLanguage);
         }
+    };
+
+    useEffect(() => {
+        invoke('get_window_options').then(applyOptions);
+        const unlisten = listen('window_options', (event) => {
+            applyOptions(event.payload);
+        });
+        return () => {
+            unlisten.then((f) => {
+                f();
+            });
+        };
     }, [serviceInstanceList, recognizeLanguage]);
 
     return (
@@ -165,9 +187,15 @@ export default function ControlArea(props) {
                 startContent={<HiTranslate className='text-[16px]' />}
                 onPress={async () => {
                     if (text) {
-                        void fetch(`http://127.0.0.1:${serverPort}/translate`, {
+                        // Wildcard addresses are reachable through loopback
+                        let host = ['0.0.0.0', '::', ''].includes(serverAddress) ? '127.0.0.1' : serverAddress;
+                        if (host.includes(':')) {
+                            host = `[${host}]`;
+                        }
+                        void fetch(`http://${host}:${serverPort}/translate`, {
                             method: 'POST',
                             body: Body.text(text),
+                            headers: serverAuth ? { Authorization: `Bearer ${serverToken}` } : {},
                             responseType: 2,
                         });
                     }
@@ -1,5 +1,6 @@
 import { Card, CardBody, CardFooter, Button, Skeleton, ButtonGroup, Tooltip } from '@nextui-org/react';
 import { sendNotification } from '@tauri-apps/api/notification';
+import { emit } from '@tauri-apps/api/event';
 import { writeText } from '@tauri-apps/api/clipboard';
 import { atom, useAtom, useAtomValue } from 'jotai';
 import React, { useEffect, useState } from 'react';
@@ -65,6 +66,11 @@ export default function TextArea(props) {
                                 }
                                 setText(v);
                                 setLoading(false);
+                                emit('recognize_result', {
+                                    service: currentServiceInstanceKey,
+                                    lang: language,
+                                    text: v,
+                                });
                                 if (autoCopy) {
                                     writeText(v).then(() => {
                                         if (hideWindow) {
@@ -106,6 +112,11 @@ export default function TextArea(props) {
                                 }
                                 setText(v);
                                 setLoading(false);
+                                emit('recognize_result', {
+                                    service: currentServiceInstanceKey,
+                                    lang: language,
+                                    text: v,
+                                });
                                 if (autoCopy) {
                                     writeText(v).then(() => {
                                         if (hideWindow) {
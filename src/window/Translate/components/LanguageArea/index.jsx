@@ -3,6 +3,8 @@ import { useTranslation } from 'react-i18next';
 import { BiTransferAlt } from 'react-icons/bi';
 import React, { useEffect } from 'react';
 import { atom, useAtom, useAtomValue } from 'jotai';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api';
 
 import { languageList } from '../../../../utils/language';
 import { detectLanguageAtom } from '../SourceArea';
@@ -22,13 +24,26 @@ export default function LanguageArea() {
     const detectLanguage = useAtomValue(detectLanguageAtom);
     const { t } = useTranslation();
 
-    useEffect(() => {
+    // Languages passed by the http server take precedence over the config
+    const applyLanguage = (options) => {
         if (translateSourceLanguage) {
-            setSourceLanguage(translateSourceLanguage);
+            setSourceLanguage(options.source ?? translateSourceLanguage);
         }
         if (translateTargetLanguage) {
-            setTargetLanguage(translateTargetLanguage);
+            setTargetLanguage(options.target ?? translateTargetLanguage);
         }
+    };
+
+    useEffect(() => {
+        invoke('get_window_options').then(applyLanguage);
+        const unlisten = listen('window_options', (event) => {
+            applyLanguage(event.payload);
+        });
+        return () => {
+            unlisten.then((f) => {
+                f();
+            });
+        };
     }, [translateSourceLanguage, translateTargetLanguage]);
 
     useEffect(() => {
@@ -33,7 +33,7 @@ import { useAtomValue } from 'jotai';
 import { nanoid } from 'nanoid';
 import { useSpring, animated } from '@react-spring/web';
 import useMeasure from 'react-use-measure';
-import { listen } from '@tauri-apps/api/event';
+import { emit, listen } from '@tauri-apps/api/event';
 import { MdSmartButton } from 'react-icons/md';
 import { HiTranslate } from 'react-icons/hi';
 import { LuDelete } from 'react-icons/lu';
@@ -84,6 +84,8 @@ export default function TargetArea(props) {
     const sourceLanguage = useAtomValue(sourceLanguageAtom);
     const targetLanguage = useAtomValue(targetLanguageAtom);
     const [autoCopy] = useConfig('translate_auto_copy', 'disable');
+    // Auto copy mode of the app rule matching the source window
+    const autoCopyOverride = useRef(null);
     const [hideWindow] = useConfig('translate_hide_window', false);
     const [clipboardMonitor] = useConfig('clipboard_monitor', false);
 
@@ -101,6 +103,20 @@ export default function TargetArea(props) {
         }
     }, [error]);
 
+    useEffect(() => {
+        invoke('get_window_options').then((options) => {
+            autoCopyOverride.current = options.auto_copy;
+        });
+        const unlistenOptions = listen('window_options', (event) => {
+            autoCopyOverride.current = event.payload.auto_copy;
+        });
+        return () => {
+            unlistenOptions.then((f) => {
+                f();
+            });
+        };
+    }, []);
+
     // listen to translation
     useEffect(() => {
         setResult('');
@@ -113,7 +129,7 @@ export default function TargetArea(props) {
             hideWindow !== null &&
             clipboardMonitor !== null
         ) {
-            if (autoCopy === 'source' && !clipboardMonitor) {
+            if ((autoCopyOverride.current ?? autoCopy) === 'source' && !clipboardMonitor) {
                 writeText(sourceText).then(() => {
                     if (hideWindow) {
                         sendNotification({ title: t('common.write_clipboard'), body: sourceText });
@@ -202,6 +218,13 @@ export default function TargetArea(props) {
                         info(`[${currentTranslateServiceInstanceKey}]resolve:` + v);
                         if (translateID[index] !== id) return;
                         setResult(typeof v === 'string' ? v.trim() : v);
+                        emit('translate_result', {
+                            text: sourceText.trim(),
+                            source: detectLanguage,
+                            target: newTargetLanguage,
+                            service: currentTranslateServiceInstanceKey,
+                            result: typeof v === 'string' ? v.trim() : v,
+                        });
                         setIsLoading(false);
                         if (v !== '') {
                             setHideOnce(false);
@@ -216,7 +239,7 @@ export default function TargetArea(props) {
                             );
                         }
                         if (index === 0 && !clipboardMonitor) {
-                            switch (autoCopy) {
+                            switch (autoCopyOverride.current ?? autoCopy) {
                                 case 'target':
                                     writeText(v).then(() => {
                                         if (hideWindow) {
@@ -275,6 +298,13 @@ export default function TargetArea(props) {
                             info(`[${currentTranslateServiceInstanceKey}]resolve:` + v);
                             if (translateID[index] !== id) return;
                             setResult(typeof v === 'string' ? v.trim() : v);
+                            emit('translate_result', {
+                                text: sourceText.trim(),
+                                source: detectLanguage,
+                                target: newTargetLanguage,
+                                service: currentTranslateServiceInstanceKey,
+                                result: typeof v === 'string' ? v.trim() : v,
+                            });
                             setIsLoading(false);
                             if (v !== '') {
                                 setHideOnce(false);
@@ -289,7 +319,7 @@ export default function TargetArea(props) {
                                 );
                             }
                             if (index === 0 && !clipboardMonitor) {
-                                switch (autoCopy) {
+                                switch (autoCopyOverride.current ?? autoCopy) {
                                     case 'target':
                                         writeText(v).then(() => {
                                             if (hideWindow) {
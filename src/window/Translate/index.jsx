@@ -2,7 +2,7 @@ import { readDir, BaseDirectory, readTextFile, exists } from '@tauri-apps/api/fs
 import { DragDropContext, Draggable, Droppable } from 'react-beautiful-dnd';
 import { appWindow, currentMonitor } from '@tauri-apps/api/window';
 import { appConfigDir, join } from '@tauri-apps/api/path';
-import { convertFileSrc } from '@tauri-apps/api/tauri';
+import { convertFileSrc, invoke } from '@tauri-apps/api/tauri';
 import { Spacer, Button } from '@nextui-org/react';
 import { AiFillCloseCircle } from 'react-icons/ai';
 import React, { useState, useEffect } from 'react';
@@ -15,6 +15,7 @@ import TargetArea from './components/TargetArea';
 import { osType } from '../../utils/env';
 import { useConfig } from '../../hooks';
 import { store } from '../../utils/store';
+import { getServiceName } from '../../utils/service_instance';
 import { info } from 'tauri-plugin-log-api';
 
 let blurTimeout = null;
@@ -81,6 +82,7 @@ export default function Translate() {
     const [collectionServiceInstanceList] = useConfig('collection_service_list', []);
     const [hideLanguage] = useConfig('hide_language', false);
     const [pined, setPined] = useState(false);
+    const [serviceFilter, setServiceFilter] = useState(null);
     const [pluginList, setPluginList] = useState(null);
     const [serviceInstanceConfigMap, setServiceInstanceConfigMap] = useState(null);
     const reorder = (list, startIndex, endIndex) => {
@@ -189,6 +191,21 @@ export default function Translate() {
         setPluginList({ ...temp });
     };
 
+    // Services passed by the http server, show all services if not set
+    useEffect(() => {
+        invoke('get_window_options').then((options) => {
+            setServiceFilter(options.services);
+        });
+        const unlistenOptions = listen('window_options', (event) => {
+            setServiceFilter(event.payload.services);
+        });
+        return () => {
+            unlistenOptions.then((f) => {
+                f();
+            });
+        };
+    }, []);
+
     useEffect(() => {
         loadPluginList();
         if (!unlisten) {
@@ -302,7 +319,11 @@ export default function Translate() {
                                             serviceInstanceConfigMap !== null &&
                                             translateServiceInstanceList.map((serviceInstanceKey, index) => {
                                                 const config = serviceInstanceConfigMap[serviceInstanceKey] ?? {};
-                                                const enable = config['enable'] ?? true;
+                                                const enable =
+                                                    (config['enable'] ?? true) &&
+                                                    (!serviceFilter ||
+                                                        serviceFilter.includes(serviceInstanceKey) ||
+                                                        serviceFilter.includes(getServiceName(serviceInstanceKey)));
 
                                                 return enable ? (
                                                     <Draggable